@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{despawn, enemy::EnemyRoster, game::Game, DataAssets, GameState, UiAssets};
+
+const BAR_WIDTH: f32 = 480.;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            ConditionSet::new()
+                .run_in_state(GameState::Playing)
+                .with_system(boss_bar_system)
+                .into(),
+        )
+        .add_exit_system(GameState::Playing, despawn::<BossBar>);
+    }
+}
+
+#[derive(Component)]
+struct BossBar;
+
+#[derive(Component)]
+struct BossBarFill;
+
+fn boss_bar_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    data_assets: Res<DataAssets>,
+    rosters: Res<Assets<EnemyRoster>>,
+    ui_assets: Res<UiAssets>,
+    bar_query: Query<Entity, With<BossBar>>,
+    mut fill_query: Query<&mut Style, With<BossBarFill>>,
+) {
+    let roster = match rosters.get(&data_assets.enemies) {
+        Some(roster) => roster,
+        None => return,
+    };
+
+    let boss = game.enemies.iter().find_map(|enemy| {
+        let archetype = &roster.archetypes[enemy.kind.0];
+
+        archetype.stats.boss.as_ref().map(|_| {
+            (
+                archetype,
+                enemy.health as f32 / archetype.stats.health.max(1) as f32,
+            )
+        })
+    });
+
+    let (archetype, health) = match boss {
+        Some(boss) => boss,
+        None => {
+            for entity in bar_query.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+
+            return;
+        }
+    };
+
+    if let Ok(mut style) = fill_query.get_single_mut() {
+        style.size.width = Val::Px(BAR_WIDTH * health);
+        return;
+    }
+
+    if !bar_query.is_empty() {
+        return;
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    justify_content: JustifyContent::FlexEnd,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect {
+                        bottom: Val::Percent(3.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                ..default()
+            },
+            BossBar,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                archetype.name.to_uppercase(),
+                TextStyle {
+                    font: ui_assets.font.clone(),
+                    font_size: 28.,
+                    color: Color::WHITE,
+                },
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(BAR_WIDTH), Val::Px(16.)),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(BAR_WIDTH * health), Val::Percent(100.)),
+                                ..default()
+                            },
+                            background_color: Color::rgb(0.8, 0.1, 0.1).into(),
+                            ..default()
+                        },
+                        BossBarFill,
+                    ));
+                });
+        });
+}
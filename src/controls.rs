@@ -0,0 +1,185 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{
+    despawn, error_handler,
+    input::{InputAction, InputBindings},
+    menu::{button_interacted, spawn_text_button, MenuState},
+    UiAssets,
+};
+
+/// The action waiting for a new key or button, if any.
+#[derive(Resource, Default, Deref, DerefMut)]
+struct Rebinding(Option<InputAction>);
+
+#[derive(Component)]
+struct OnControls;
+
+#[derive(Component)]
+struct RebindButton(InputAction);
+
+#[derive(Component)]
+struct RebindText(InputAction);
+
+#[derive(Component)]
+struct ResetBindingsButton;
+
+#[derive(Component)]
+struct BackButton;
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>()
+            .add_enter_system(MenuState::Controls, controls_setup_system)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(MenuState::Controls)
+                    .with_system(rebind_button_system)
+                    .with_system(rebind_capture_system)
+                    .with_system(rebind_text_system)
+                    .with_system(button_reset.run_if(button_interacted::<ResetBindingsButton>))
+                    .with_system(button_back.run_if(button_interacted::<BackButton>))
+                    .into(),
+            )
+            .add_exit_system(
+                MenuState::Controls,
+                save_bindings_system.chain(error_handler),
+            )
+            .add_exit_system(MenuState::Controls, despawn::<OnControls>);
+    }
+}
+
+fn controls_setup_system(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    bindings: Res<InputBindings>,
+) {
+    commands.insert_resource(Rebinding::default());
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            OnControls,
+        ))
+        .with_children(|parent| {
+            for action in InputAction::ALL {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                size: Size::new(Val::Px(600.), Val::Px(34.)),
+                                margin: UiRect {
+                                    top: Val::Px(6.),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                            ..default()
+                        },
+                        RebindButton(action),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(
+                                binding_label(action, &bindings, None),
+                                TextStyle {
+                                    font: ui_assets.font.clone(),
+                                    font_size: 24.,
+                                    color: Color::WHITE,
+                                },
+                            ),
+                            RebindText(action),
+                        ));
+                    });
+            }
+
+            spawn_text_button(parent, "RESET", &ui_assets, ResetBindingsButton);
+            spawn_text_button(parent, "BACK", &ui_assets, BackButton);
+        });
+}
+
+fn binding_label(
+    action: InputAction,
+    bindings: &InputBindings,
+    rebinding: Option<InputAction>,
+) -> String {
+    match rebinding == Some(action) {
+        true => format!("{}: PRESS A KEY OR BUTTON, ESC TO CANCEL", action.label()),
+        false => format!("{}: {}", action.label(), bindings.get(action).label()),
+    }
+}
+
+fn rebind_button_system(
+    mut rebinding: ResMut<Rebinding>,
+    query: Query<(&Interaction, &RebindButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction == Interaction::Clicked {
+            rebinding.0 = Some(button.0);
+        }
+    }
+}
+
+fn rebind_capture_system(
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<InputBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+) {
+    let action = match rebinding.0 {
+        Some(action) => action,
+        None => return,
+    };
+
+    // Escape can't be bound so there is always a way out
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        rebinding.0 = None;
+    } else if let Some(key) = keyboard_input.get_just_pressed().next() {
+        bindings.bind_key(action, *key);
+        rebinding.0 = None;
+    } else if let Some(button) = gamepad_input.get_just_pressed().next() {
+        bindings.bind_button(action, button.button_type);
+        rebinding.0 = None;
+    }
+}
+
+fn rebind_text_system(
+    rebinding: Res<Rebinding>,
+    bindings: Res<InputBindings>,
+    mut query: Query<(&mut Text, &RebindText)>,
+) {
+    if !rebinding.is_changed() && !bindings.is_changed() {
+        return;
+    }
+
+    for (mut text, rebind_text) in query.iter_mut() {
+        text.sections[0].value = binding_label(rebind_text.0, &bindings, rebinding.0);
+    }
+}
+
+fn button_reset(mut bindings: ResMut<InputBindings>, mut rebinding: ResMut<Rebinding>) {
+    *bindings = InputBindings::default();
+    rebinding.0 = None;
+}
+
+fn button_back(mut commands: Commands) {
+    commands.insert_resource(NextState(MenuState::Main));
+}
+
+fn save_bindings_system(bindings: Res<InputBindings>) -> Result<(), String> {
+    bindings.save()
+}
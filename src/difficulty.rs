@@ -0,0 +1,67 @@
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use serde::Deserialize;
+
+use crate::{
+    simulation::{
+        difficulty::{Difficulty, DifficultyCurve},
+        speed::SpeedConfig,
+    },
+    DataAssets,
+};
+
+/// A difficulty preset read from a `.difficulty.ron` file.
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "b7e1c4d8-5a29-4f63-9e0b-2c8d61f4a3e7"]
+pub struct DifficultyAsset {
+    #[serde(default)]
+    pub speed: SpeedConfig,
+    #[serde(default)]
+    pub curve: DifficultyCurve,
+}
+
+impl DataAssets {
+    pub fn difficulty(&self, difficulty: Difficulty) -> &Handle<DifficultyAsset> {
+        match difficulty {
+            Difficulty::Easy => &self.easy,
+            Difficulty::Normal => &self.normal,
+            Difficulty::Hard => &self.hard,
+            Difficulty::Custom => &self.custom,
+        }
+    }
+}
+
+#[derive(Default)]
+struct DifficultyLoader;
+
+impl AssetLoader for DifficultyLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let difficulty = ron::de::from_bytes::<DifficultyAsset>(bytes)?;
+
+            load_context.set_default_asset(LoadedAsset::new(difficulty));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["difficulty.ron"]
+    }
+}
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<DifficultyAsset>()
+            .init_asset_loader::<DifficultyLoader>();
+    }
+}
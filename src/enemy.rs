@@ -1,489 +1,429 @@
-use bevy::{ecs::system::Command, prelude::*};
+use std::{collections::HashSet, path::PathBuf};
+
+use bevy::{
+    asset::{AssetLoader, AssetPath, BoxedFuture, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+};
 use bevy_kira_audio::prelude::*;
 use iyes_loopless::prelude::*;
-use rand::{seq::SliceRandom, Rng};
+use serde::Deserialize;
 
 use crate::{
-    despawn, level::LEVEL_SIZE, music::Gameplay, snake::Snake, AudioAssets, GameState, Position,
-    TextureAssets,
+    despawn,
+    game::Game,
+    music::{Gameplay, Mixer, SoundCategory},
+    simulation::{
+        enemy::{EnemyId, EnemyPose, EnemyStats},
+        projectile::{Owner, ProjectileId},
+        SimulationEvent,
+    },
+    DataAssets, GameState, TextureAssets,
 };
 
-const MAX_ENEMIES: usize = 1;
-const ENEMY_DECISION_TIME_MIN: f32 = 0.5;
-const ENEMY_DECISION_TIME_MAX: f32 = 1.5;
-const ENEMY_ATTACK_SPEED: f32 = 5.0;
-
-pub struct EnemyPlugin;
+const ENEMY_TILE_SIZE: f32 = 16.;
+const HIT_FLASH_TIME: f32 = 0.3;
 
-impl Plugin for EnemyPlugin {
-    fn build(&self, app: &mut App) {
-        app.init_resource::<MaxEnemies>()
-            .add_system_set(
-                ConditionSet::new()
-                    .run_in_state(GameState::Playing)
-                    .with_system(spawn_enemy_system)
-                    .with_system(enemy_state_management_system)
-                    .with_system(move_enemy_system)
-                    .with_system(map_enemy_position)
-                    .with_system(enemy_attack_animation_system)
-                    .with_system(enemy_attack_system)
-                    .with_system(enemy_attack_move_system)
-                    .into(),
-            )
-            .add_exit_system(GameState::Playing, despawn::<Enemy>)
-            .add_exit_system(GameState::Playing, despawn::<EnemyAttack>);
-    }
+#[derive(Deserialize)]
+struct SpriteSheetDefinition {
+    path: String,
+    columns: usize,
+    rows: usize,
 }
 
-#[derive(Resource, Deref, DerefMut)]
-pub struct MaxEnemies(pub usize);
-
-impl Default for MaxEnemies {
-    fn default() -> Self {
-        MaxEnemies(MAX_ENEMIES)
-    }
+#[derive(Deserialize)]
+struct SoundDefinition {
+    path: String,
+    gain: f64,
 }
 
-#[derive(Component)]
-pub struct Enemy {
-    decision_timer: Timer,
-    atk_anim_timer: Timer,
-    move_step_timer: Timer,
+#[derive(Deserialize, Default)]
+struct EnemySoundsDefinition {
+    wind_up: Option<SoundDefinition>,
+    attack: Option<SoundDefinition>,
 }
 
-impl Enemy {
-    fn reset_decision_timer(&mut self) {
-        let mut rng = rand::thread_rng();
-
-        self.decision_timer = Timer::from_seconds(
-            rng.gen_range(ENEMY_DECISION_TIME_MIN..ENEMY_DECISION_TIME_MAX),
-            TimerMode::Once,
-        );
-    }
-
-    fn reset_attack_animation_timer(&mut self, enemy_type: &EnemyType) {
-        let time = match enemy_type {
-            EnemyType::Wizard => 0.5,
-            EnemyType::Knight => 2.5,
-        };
-
-        self.atk_anim_timer = Timer::from_seconds(time, TimerMode::Once);
-    }
+#[derive(Deserialize)]
+struct EnemyDefinition {
+    name: String,
+    sprite_sheet: SpriteSheetDefinition,
+    #[serde(default)]
+    sounds: EnemySoundsDefinition,
+    stats: EnemyStats,
 }
 
-impl From<EnemyType> for Enemy {
-    fn from(enemy_type: EnemyType) -> Self {
-        let mut rng = rand::thread_rng();
-
-        match enemy_type {
-            EnemyType::Wizard => Enemy {
-                decision_timer: Timer::from_seconds(
-                    rng.gen_range(ENEMY_DECISION_TIME_MIN..ENEMY_DECISION_TIME_MAX),
-                    TimerMode::Once,
-                ),
-                atk_anim_timer: Timer::from_seconds(0.5, TimerMode::Once),
-                move_step_timer: Timer::from_seconds(0.25, TimerMode::Repeating),
-            },
-            EnemyType::Knight => Enemy {
-                decision_timer: Timer::from_seconds(
-                    rng.gen_range(
-                        (ENEMY_DECISION_TIME_MIN / 1.25)..(ENEMY_DECISION_TIME_MAX / 1.25),
-                    ),
-                    TimerMode::Once,
-                ),
-                atk_anim_timer: Timer::from_seconds(2.5, TimerMode::Once),
-                move_step_timer: Timer::from_seconds(0.25, TimerMode::Repeating),
-            },
-        }
-    }
+#[derive(Deserialize)]
+struct EnemyRosterDefinition {
+    archetypes: Vec<EnemyDefinition>,
 }
 
-struct SpawnEnemy;
-
-impl Command for SpawnEnemy {
-    fn write(self, world: &mut World) {
-        let snake = world.get_resource::<Snake>().unwrap().clone();
-
-        let mut position = random_position();
-        while snake.segments.contains(&position) {
-            position = random_position()
-        }
-
-        let assets = world.get_resource::<TextureAssets>().unwrap();
-
-        let enemy_type = match rand::random::<bool>() {
-            true => EnemyType::Wizard,
-            false => EnemyType::Knight,
-        };
-
-        world.spawn((
-            Enemy::from(enemy_type.clone()),
-            position,
-            SpriteSheetBundle {
-                texture_atlas: match enemy_type {
-                    EnemyType::Wizard => assets.wizard_sheet.clone(),
-                    EnemyType::Knight => assets.knight_sheet.clone(),
-                },
-                transform: Transform::from_xyz(position.x as f32, position.y as f32, 1.5),
-                sprite: TextureAtlasSprite {
-                    custom_size: Some(Vec2::new(1., 1.)),
-                    ..default()
-                },
-                ..default()
-            },
-            Target(None),
-            EnemyState::Idle,
-            enemy_type,
-        ));
-    }
+pub struct EnemySound {
+    pub handle: Handle<AudioSource>,
+    pub gain: f64,
 }
 
-#[derive(Component, Clone, PartialEq, Eq)]
-enum EnemyType {
-    Knight,
-    Wizard,
+pub struct EnemyArchetype {
+    pub name: String,
+    pub stats: EnemyStats,
+    pub sprite_sheet: Handle<TextureAtlas>,
+    pub wind_up_sound: Option<EnemySound>,
+    pub attack_sound: Option<EnemySound>,
 }
 
-#[derive(Component, PartialEq, Eq, Clone, Debug)]
-enum EnemyState {
-    Idle,
-    AttackAnimation,
-    Attacking,
-    Moving,
+/// Every enemy that can show up in a run, read from an `.enemies.ron` file.
+/// The simulation only gets the stats, in the same order, so an `EnemyKind`
+/// indexes straight into `archetypes`.
+#[derive(TypeUuid)]
+#[uuid = "57dda082-8644-4fb6-a73e-1a089727f5b4"]
+pub struct EnemyRoster {
+    pub archetypes: Vec<EnemyArchetype>,
 }
 
-impl EnemyState {
-    fn is_attack_animation(&self) -> bool {
-        *self == EnemyState::AttackAnimation
+impl EnemyRoster {
+    pub fn stats(&self) -> Vec<EnemyStats> {
+        self.archetypes
+            .iter()
+            .map(|archetype| archetype.stats.clone())
+            .collect()
     }
+}
 
-    fn is_attacking(&self) -> bool {
-        *self == EnemyState::Attacking
-    }
+#[derive(Default)]
+struct EnemyRosterLoader;
+
+impl AssetLoader for EnemyRosterLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let definition = ron::de::from_bytes::<EnemyRosterDefinition>(bytes)?;
+            let mut dependencies = Vec::new();
+            let mut archetypes = Vec::new();
+
+            let mut load_sound = |load_context: &mut LoadContext,
+                                  sound: Option<SoundDefinition>| {
+                sound.map(|sound| {
+                    let path = AssetPath::new(PathBuf::from(sound.path), None);
+                    dependencies.push(path.clone());
+
+                    EnemySound {
+                        handle: load_context.get_handle(path),
+                        gain: sound.gain,
+                    }
+                })
+            };
+
+            for enemy in definition.archetypes {
+                let wind_up_sound = load_sound(load_context, enemy.sounds.wind_up);
+                let attack_sound = load_sound(load_context, enemy.sounds.attack);
+
+                let texture_path = AssetPath::new(PathBuf::from(enemy.sprite_sheet.path), None);
+                let texture_atlas = TextureAtlas::from_grid(
+                    load_context.get_handle(texture_path.clone()),
+                    Vec2::splat(ENEMY_TILE_SIZE),
+                    enemy.sprite_sheet.columns,
+                    enemy.sprite_sheet.rows,
+                    None,
+                    None,
+                );
+                let sprite_sheet = load_context.set_labeled_asset(
+                    &enemy.name,
+                    LoadedAsset::new(texture_atlas).with_dependency(texture_path),
+                );
+
+                archetypes.push(EnemyArchetype {
+                    name: enemy.name,
+                    stats: enemy.stats,
+                    sprite_sheet,
+                    wind_up_sound,
+                    attack_sound,
+                });
+            }
 
-    fn is_moving(&self) -> bool {
-        *self == EnemyState::Moving
-    }
+            load_context.set_default_asset(
+                LoadedAsset::new(EnemyRoster { archetypes }).with_dependencies(dependencies),
+            );
 
-    fn is_idle(&self) -> bool {
-        *self == EnemyState::Idle
+            Ok(())
+        })
     }
 
-    fn to_idle(&mut self) {
-        *self = EnemyState::Idle;
+    fn extensions(&self) -> &[&str] {
+        &["enemies.ron"]
     }
+}
 
-    fn to_attacking(&mut self) {
-        *self = EnemyState::Attacking;
-    }
+pub struct EnemyPlugin;
 
-    fn randomize() -> Self {
-        let mut rng = rand::thread_rng();
-        let states = [
-            EnemyState::Idle,
-            EnemyState::AttackAnimation,
-            EnemyState::Moving,
-        ];
-        states
-            .choose_weighted(&mut rng, |state| match state {
-                EnemyState::Idle => 5,
-                EnemyState::AttackAnimation => 2,
-                EnemyState::Moving => 3,
-                _ => 0,
-            })
-            .unwrap()
-            .clone()
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<EnemyRoster>()
+            .init_asset_loader::<EnemyRosterLoader>()
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Playing)
+                    .with_system(draw_enemies_system)
+                    .with_system(draw_enemy_attacks_system)
+                    .with_system(draw_danger_tiles_system)
+                    .with_system(enemy_hit_flash_system)
+                    .with_system(enemy_sound_system)
+                    .into(),
+            )
+            .add_exit_system(GameState::Playing, despawn::<Enemy>)
+            .add_exit_system(GameState::Playing, despawn::<EnemyAttack>)
+            .add_exit_system(GameState::Playing, despawn::<DangerTile>);
     }
 }
 
 #[derive(Component)]
-pub struct EnemyAttack {
-    direction: Vec2,
-}
+pub struct Enemy(EnemyId);
+
+#[derive(Component)]
+pub struct EnemyAttack(ProjectileId);
 
+/// Tints an enemy that was just bitten.
 #[derive(Component, Deref, DerefMut)]
-struct Target(Option<Position>);
+struct HitFlash(Timer);
 
-fn random_position() -> Position {
-    let mut rng = rand::thread_rng();
-    let x = rng.gen_range(-LEVEL_SIZE.x..=LEVEL_SIZE.x);
-    let y = rng.gen_range(-LEVEL_SIZE.y..=LEVEL_SIZE.y);
-    Position { x, y }
-}
+/// Marks the tile a melee attack is about to land on.
+#[derive(Component)]
+pub struct DangerTile(EnemyId);
 
-fn spawn_enemy_system(
+fn draw_enemies_system(
     mut commands: Commands,
-    enemies: Query<&Enemy>,
-    max_enemies: Res<MaxEnemies>,
+    game: Res<Game>,
+    data_assets: Res<DataAssets>,
+    rosters: Res<Assets<EnemyRoster>>,
+    mut enemy_query: Query<(Entity, &Enemy, &mut Transform, &mut TextureAtlasSprite)>,
 ) {
-    let enemies_count = enemies.iter().count();
+    let mut drawn = HashSet::new();
+
+    for (entity, enemy, mut transform, mut sprite) in enemy_query.iter_mut() {
+        let state = match game.enemies.iter().find(|state| state.id == enemy.0) {
+            Some(state) => state,
+            None => {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        };
 
-    if enemies_count >= max_enemies.0 {
-        return;
-    }
+        transform.translation.x = state.position.x as f32;
+        transform.translation.y = state.position.y as f32;
+        sprite.index = pose_index(&state.pose);
 
-    commands.add(SpawnEnemy);
-}
+        drawn.insert(enemy.0);
+    }
 
-fn enemy_state_management_system(
-    time: Res<Time>,
-    audio_assets: Res<AudioAssets>,
-    gameplay_channel: Res<AudioChannel<Gameplay>>,
-    mut enemy_query: Query<(
-        &mut Enemy,
-        &EnemyType,
-        &mut EnemyState,
-        &mut TextureAtlasSprite,
-    )>,
-) {
-    for (mut enemy, enemy_type, mut enemy_state, mut sprite) in enemy_query.iter_mut() {
-        if !enemy_state.is_idle() {
-            continue;
-        }
+    let roster = match rosters.get(&data_assets.enemies) {
+        Some(roster) => roster,
+        None => return,
+    };
 
-        if !enemy.decision_timer.tick(time.delta()).just_finished() {
+    for state in game.enemies.iter() {
+        if drawn.contains(&state.id) {
             continue;
         }
 
-        let new_state = EnemyState::randomize();
+        let mut hit_flash = Timer::from_seconds(HIT_FLASH_TIME, TimerMode::Once);
+        hit_flash.tick(hit_flash.duration());
 
-        match new_state {
-            EnemyState::Idle => {
-                sprite.index = 0;
-                enemy.reset_decision_timer()
-            }
-            EnemyState::AttackAnimation => match enemy_type {
-                EnemyType::Wizard => {
-                    sprite.index = 2;
-                    gameplay_channel
-                        .play(audio_assets.wizard_prepare.clone())
-                        .with_volume(0.25);
-                }
-                EnemyType::Knight => {}
+        commands.spawn((
+            Enemy(state.id),
+            HitFlash(hit_flash),
+            SpriteSheetBundle {
+                texture_atlas: roster.archetypes[state.kind.0].sprite_sheet.clone(),
+                transform: Transform::from_xyz(
+                    state.position.x as f32,
+                    state.position.y as f32,
+                    1.5,
+                ),
+                sprite: TextureAtlasSprite {
+                    index: pose_index(&state.pose),
+                    custom_size: Some(Vec2::new(1., 1.)),
+                    ..default()
+                },
+                ..default()
             },
-            _ => {}
-        }
+        ));
+    }
+}
 
-        *enemy_state = new_state;
+fn pose_index(pose: &EnemyPose) -> usize {
+    match pose {
+        EnemyPose::Idle => 0,
+        EnemyPose::FacingRight => 1,
+        EnemyPose::Attacking => 2,
     }
 }
 
-fn move_enemy_system(
-    time: Res<Time>,
-    mut enemy_query: Query<(
-        &mut Enemy,
-        &mut EnemyState,
-        &EnemyType,
-        &mut Target,
-        &mut Position,
-        &mut TextureAtlasSprite,
-    )>,
-    snake: Res<Snake>,
+fn draw_enemy_attacks_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    assets: Res<TextureAssets>,
+    mut enemy_attack_query: Query<(Entity, &EnemyAttack, &mut Transform)>,
 ) {
-    for (mut enemy, mut enemy_state, enemy_type, mut target, mut position, mut sprite) in
-        enemy_query.iter_mut()
-    {
-        if !enemy_state.is_moving() {
-            continue;
-        }
-
-        if target.is_none() {
-            match enemy_type {
-                _ => {
-                    let mut rng = rand::thread_rng();
-                    let x = rng.gen_range(-LEVEL_SIZE.x..LEVEL_SIZE.x);
-                    let y = rng.gen_range(-LEVEL_SIZE.y..LEVEL_SIZE.y);
+    let mut drawn = HashSet::new();
 
-                    *target = Target(Some(Position { x, y }));
-                }
+    for (entity, enemy_attack, mut transform) in enemy_attack_query.iter_mut() {
+        let projectile = match game
+            .projectiles
+            .iter()
+            .find(|projectile| projectile.id == enemy_attack.0)
+        {
+            Some(projectile) => projectile,
+            None => {
+                commands.entity(entity).despawn_recursive();
+                continue;
             }
+        };
 
-            continue;
-        }
-
-        if !enemy.move_step_timer.tick(time.delta()).just_finished() {
-            continue;
-        }
+        transform.translation.x = projectile.position.x;
+        transform.translation.y = projectile.position.y;
+        // Homing projectiles turn while they fly
+        transform.rotation = projectile_rotation(projectile.direction);
 
-        let target_position = target.unwrap();
+        drawn.insert(enemy_attack.0);
+    }
 
-        if *position == target_position {
-            *target = Target(None);
-            enemy_state.to_idle();
-            enemy.reset_decision_timer();
+    for projectile in game.projectiles.iter() {
+        if drawn.contains(&projectile.id) {
             continue;
         }
 
-        let old_position = position.clone();
-
-        if position.x < target_position.x {
-            position.x += 1;
-            sprite.index = 1;
-        } else if position.x > target_position.x {
-            position.x -= 1;
-            sprite.index = 0;
-        }
-
-        if position.y < target_position.y {
-            position.y += 1;
-        } else if position.y > target_position.y {
-            position.y -= 1;
-        }
-
-        let any_segments_in_position = snake
-            .segments
-            .iter()
-            .any(|segment_position| *segment_position == *position);
-
-        if any_segments_in_position {
-            *target = Target(None);
-            *position = old_position;
-            enemy_state.to_idle();
-            enemy.reset_decision_timer();
-        }
+        let transform = Transform::from_xyz(projectile.position.x, projectile.position.y, 1.5)
+            .with_rotation(projectile_rotation(projectile.direction));
+
+        commands.spawn((
+            SpriteBundle {
+                texture: assets.projectile.clone(),
+                sprite: Sprite {
+                    color: match projectile.owner {
+                        Owner::Enemy => Color::WHITE,
+                        // The snake's spit reuses the same sprite
+                        Owner::Snake => Color::rgb(0.4, 1., 0.4),
+                    },
+                    custom_size: Some(Vec2::splat(projectile.size)),
+                    ..default()
+                },
+                transform,
+                ..default()
+            },
+            EnemyAttack(projectile.id),
+        ));
     }
 }
 
-fn map_enemy_position(mut enemies: Query<(&Position, &mut Transform)>) {
-    for (position, mut transform) in enemies.iter_mut() {
-        transform.translation.x = position.x as f32;
-        transform.translation.y = position.y as f32;
-    }
+/// The projectile sprite points to the top right.
+fn projectile_rotation(direction: Vec2) -> Quat {
+    Quat::from_rotation_z(Vec2::new(1., 1.).angle_between(direction))
 }
 
-fn enemy_attack_animation_system(
-    time: Res<Time>,
-    snake: Res<Snake>,
-    mut enemy_query: Query<(
-        &mut Enemy,
-        &mut EnemyState,
-        &EnemyType,
-        &Position,
-        &mut TextureAtlasSprite,
-    )>,
-) {
-    for (mut enemy, mut enemy_state, enemy_type, position, mut sprite) in enemy_query.iter_mut() {
-        if !enemy_state.is_attack_animation() {
-            continue;
-        }
+fn danger_color(progress: f32) -> Color {
+    Color::rgba(0.9, 0.1, 0.1, 0.2 + 0.5 * progress)
+}
 
-        if !enemy.atk_anim_timer.tick(time.delta()).just_finished() {
-            for segment in snake.segments.iter() {
-                // If there is a segment directly below the knight, attack
-                if segment.x == position.x && segment.y == position.y - 1 {
-                    // gameplay_channel.play(audio_assets.knight_attack.clone());
-                    enemy_state.to_attacking();
-                    enemy.reset_attack_animation_timer(enemy_type);
-                }
+fn draw_danger_tiles_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    mut danger_tile_query: Query<(Entity, &DangerTile, &mut Transform, &mut Sprite)>,
+) {
+    let mut drawn = HashSet::new();
+
+    for (entity, danger_tile, mut transform, mut sprite) in danger_tile_query.iter_mut() {
+        let (enemy, telegraph) = match game.enemies.iter().find_map(|enemy| {
+            enemy
+                .telegraph
+                .filter(|_| enemy.id == danger_tile.0)
+                .map(|telegraph| (enemy, telegraph))
+        }) {
+            Some(found) => found,
+            None => {
+                commands.entity(entity).despawn_recursive();
+                continue;
             }
+        };
 
-            continue;
-        }
+        transform.translation.x = telegraph.x as f32;
+        transform.translation.y = telegraph.y as f32;
+        sprite.color = danger_color(enemy.wind_up_progress());
+
+        drawn.insert(danger_tile.0);
+    }
+
+    for enemy in game.enemies.iter() {
+        let telegraph = match enemy.telegraph {
+            Some(telegraph) if !drawn.contains(&enemy.id) => telegraph,
+            _ => continue,
+        };
 
-        enemy_state.to_attacking();
-        enemy.reset_attack_animation_timer(enemy_type);
-        sprite.index = 0;
+        commands.spawn((
+            DangerTile(enemy.id),
+            SpriteBundle {
+                sprite: Sprite {
+                    color: danger_color(enemy.wind_up_progress()),
+                    custom_size: Some(Vec2::new(1., 1.)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(telegraph.x as f32, telegraph.y as f32, 1.3),
+                ..default()
+            },
+        ));
     }
 }
 
-fn enemy_attack_system(
-    assets: Res<TextureAssets>,
-    audio_assets: Res<AudioAssets>,
-    gameplay_channel: Res<AudioChannel<Gameplay>>,
-    mut snake: ResMut<Snake>,
-    mut commands: Commands,
-    mut enemy_query: Query<
-        (
-            &mut Enemy,
-            &mut EnemyState,
-            &mut TextureAtlasSprite,
-            &Position,
-            &EnemyType,
-            &Transform,
-        ),
-        With<Enemy>,
-    >,
+fn enemy_hit_flash_system(
+    mut events: EventReader<SimulationEvent>,
+    time: Res<Time>,
+    mut enemy_query: Query<(&Enemy, &mut HitFlash, &mut TextureAtlasSprite)>,
 ) {
-    for (mut enemy, mut enemy_state, mut sprite, position, enemy_type, transform) in
-        enemy_query.iter_mut()
-    {
-        if !enemy_state.is_attacking() {
-            continue;
+    let hits = events
+        .iter()
+        .filter_map(|event| match event {
+            SimulationEvent::EnemyHit { id, .. } => Some(*id),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    for (enemy, mut flash, mut sprite) in enemy_query.iter_mut() {
+        if hits.contains(&enemy.0) {
+            flash.reset();
         }
 
-        match enemy_type {
-            EnemyType::Wizard => {
-                let mut rng = rand::thread_rng();
-                let segments = snake.segments.iter().collect::<Vec<_>>();
-                let segment_position = segments.choose(&mut rng).unwrap();
-                let mut transform = *transform;
-                let direction = Vec2::new(
-                    segment_position.x as f32 - transform.translation.x,
-                    segment_position.y as f32 - transform.translation.y,
-                )
-                .normalize();
-
-                transform.rotate(Quat::from_rotation_z(
-                    Vec2::new(1., 1.).angle_between(direction),
-                ));
-
-                commands.spawn((
-                    SpriteBundle {
-                        texture: assets.projectile.clone(),
-                        sprite: Sprite {
-                            custom_size: Some(Vec2::new(0.5, 0.5)),
-                            ..default()
-                        },
-                        transform,
-                        ..default()
-                    },
-                    EnemyAttack { direction },
-                ));
-
-                gameplay_channel.play(audio_assets.wizard_attack.clone());
-            }
-            EnemyType::Knight => {
-                sprite.index = 2;
-                gameplay_channel
-                    .play(audio_assets.knight_attack.clone())
-                    .with_volume(0.25);
-                let mut damaged = false;
-
-                let segments = snake.segments.iter().collect::<Vec<_>>();
-                for segment in segments {
-                    if segment.x == position.x && segment.y == position.y - 1 {
-                        damaged = true;
-                    }
-                }
-
-                if damaged {
-                    snake.damage(1);
-                    if snake.is_dead() {
-                        commands.insert_resource(NextState(GameState::GameOver));
-                    }
-                }
-            }
-        }
+        flash.tick(time.delta());
 
-        enemy_state.to_idle();
-        enemy.reset_decision_timer();
+        // Blink between red and the normal colors while it lasts
+        sprite.color = match flash.finished() || (flash.percent() * 6.) as u32 % 2 == 1 {
+            true => Color::WHITE,
+            false => Color::rgb(1., 0.3, 0.3),
+        };
     }
 }
 
-fn enemy_attack_move_system(
-    time: Res<Time>,
-    mut commands: Commands,
-    mut enemy_attack_query: Query<(Entity, &EnemyAttack, &mut Transform)>,
+fn enemy_sound_system(
+    mut events: EventReader<SimulationEvent>,
+    data_assets: Res<DataAssets>,
+    rosters: Res<Assets<EnemyRoster>>,
+    mixer: Res<Mixer>,
+    gameplay_channel: Res<AudioChannel<Gameplay>>,
 ) {
-    // Move in the direction of the target
-    for (entity, enemy_attack, mut transform) in enemy_attack_query.iter_mut() {
-        let movement_vector = enemy_attack.direction * ENEMY_ATTACK_SPEED * time.delta_seconds();
-
-        transform.translation.x += movement_vector.x;
-        transform.translation.y += movement_vector.y;
+    let roster = match rosters.get(&data_assets.enemies) {
+        Some(roster) => roster,
+        None => return,
+    };
+
+    for event in events.iter() {
+        let sound = match event {
+            SimulationEvent::EnemyWindUp { kind, .. } => &roster.archetypes[kind.0].wind_up_sound,
+            SimulationEvent::EnemyAttacked { kind, .. } => &roster.archetypes[kind.0].attack_sound,
+            _ => continue,
+        };
 
-        if !Position::from(transform.translation.truncate()).in_world() {
-            commands.entity(entity).despawn();
+        if let Some(sound) = sound {
+            gameplay_channel
+                .play(sound.handle.clone())
+                .with_volume(sound.gain * mixer.category_gain(SoundCategory::Enemies));
         }
     }
 }
@@ -1,20 +1,157 @@
 use bevy::prelude::*;
+use iyes_loopless::prelude::*;
 
 use crate::{
-    enemy::EnemyPlugin, level::LevelPlugin, menu::MenuPlugin, music::MusicPlugin,
-    score::ScorePlugin, snake::SnakePlugin, splash::SplashPlugin,
+    boss::BossPlugin,
+    controls::ControlsPlugin,
+    difficulty::{DifficultyAsset, DifficultyPlugin},
+    enemy::{EnemyPlugin, EnemyRoster},
+    input::InputActionPlugin,
+    leaderboard::LeaderboardPlugin,
+    level::{LevelAsset, LevelPlugin},
+    menu::MenuPlugin,
+    music::MusicPlugin,
+    pause::{PausePlugin, PauseState},
+    pickup::{PickupPlugin, PickupsAsset},
+    replay::{Playback, ReplayPlugin},
+    score::ScorePlugin,
+    seed::{Seed, SeedPlugin},
+    settings::{Settings, SettingsPlugin},
+    simulation::{
+        director::DirectorConfig, Simulation, SimulationConfig, SimulationEvent, TickInput,
+    },
+    snake::SnakePlugin,
+    splash::SplashPlugin,
+    upgrade::{UpgradePlugin, UpgradeRegistry},
+    waves::{WavesAsset, WavesPlugin},
+    DataAssets, GameState,
 };
 
+#[derive(Resource, Deref, DerefMut)]
+pub struct Game(pub Simulation);
+
+impl Default for Game {
+    fn default() -> Self {
+        Self(Simulation::new(rand::random(), SimulationConfig::default()))
+    }
+}
+
+/// The input handed to the simulation on its next tick.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct NextInput(pub TickInput);
+
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(LevelPlugin)
+        app.init_resource::<Game>()
+            .init_resource::<NextInput>()
+            .add_event::<SimulationEvent>()
+            .add_enter_system(GameState::Playing, reset_game_system)
+            .add_fixed_timestep(Simulation::timestep(), "simulation")
+            .add_fixed_timestep_system(
+                "simulation",
+                0,
+                simulation_tick_system
+                    .run_in_state(GameState::Playing)
+                    .run_in_state(PauseState::Running)
+                    .label("tick"),
+            )
+            .add_plugin(InputActionPlugin)
+            .add_plugin(BossPlugin)
+            .add_plugin(ControlsPlugin)
+            .add_plugin(DifficultyPlugin)
+            .add_plugin(LeaderboardPlugin)
+            .add_plugin(LevelPlugin)
             .add_plugin(SnakePlugin)
             .add_plugin(EnemyPlugin)
             .add_plugin(MenuPlugin)
             .add_plugin(MusicPlugin)
+            .add_plugin(PausePlugin)
+            .add_plugin(PickupPlugin)
+            .add_plugin(ReplayPlugin)
             .add_plugin(ScorePlugin)
-            .add_plugin(SplashPlugin);
+            .add_plugin(SeedPlugin)
+            .add_plugin(SettingsPlugin)
+            .add_plugin(SplashPlugin)
+            .add_plugin(UpgradePlugin)
+            .add_plugin(WavesPlugin);
+    }
+}
+
+fn reset_game_system(
+    mut commands: Commands,
+    seed: Res<Seed>,
+    settings: Res<Settings>,
+    mut playback: Option<ResMut<Playback>>,
+    data_assets: Res<DataAssets>,
+    rosters: Res<Assets<EnemyRoster>>,
+    levels: Res<Assets<LevelAsset>>,
+    waves: Res<Assets<WavesAsset>>,
+    upgrades: Res<Assets<UpgradeRegistry>>,
+    pickups: Res<Assets<PickupsAsset>>,
+    difficulties: Res<Assets<DifficultyAsset>>,
+) {
+    let (seed, difficulty) = match &mut playback {
+        // A restart plays the replay again from its first input
+        Some(playback) => {
+            playback.rewind();
+            (playback.replay.seed, playback.replay.difficulty)
+        }
+        None => (
+            seed.value().unwrap_or_else(rand::random),
+            settings.difficulty,
+        ),
+    };
+
+    let roster = rosters.get(&data_assets.enemies);
+    let preset = difficulties.get(data_assets.difficulty(difficulty));
+    // Replays bring the speed and curve they were played with
+    let (speed, curve) = match (&playback, preset) {
+        (Some(playback), _) => (playback.replay.speed.clone(), playback.replay.curve.clone()),
+        (None, Some(preset)) => (preset.speed.clone(), preset.curve.clone()),
+        (None, None) => default(),
+    };
+    let config = SimulationConfig {
+        enemies: roster.map(EnemyRoster::stats).unwrap_or_default(),
+        level: levels
+            .get(&data_assets.level)
+            .map(|level| level.0.clone())
+            .unwrap_or_default(),
+        director: match (waves.get(&data_assets.waves), roster) {
+            (Some(waves), Some(roster)) => waves.director_config(roster),
+            _ => DirectorConfig::default(),
+        },
+        upgrades: upgrades
+            .get(&data_assets.upgrades)
+            .map(UpgradeRegistry::stats)
+            .unwrap_or_default(),
+        pickups: pickups
+            .get(&data_assets.pickups)
+            .map(PickupsAsset::config)
+            .unwrap_or_default(),
+        speed,
+        difficulty,
+        curve,
+    };
+
+    commands.insert_resource(Game(Simulation::new(seed, config)));
+    commands.insert_resource(NextInput::default());
+}
+
+fn simulation_tick_system(
+    mut commands: Commands,
+    mut game: ResMut<Game>,
+    mut input: ResMut<NextInput>,
+    mut events: EventWriter<SimulationEvent>,
+) {
+    let tick_events = game.tick(std::mem::take(&mut input.0));
+
+    for event in tick_events {
+        if let SimulationEvent::SnakeDied { .. } = event {
+            commands.insert_resource(NextState(GameState::GameOver));
+        }
+
+        events.send(event);
     }
 }
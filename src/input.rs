@@ -0,0 +1,285 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+const BINDINGS_KEY: &str = "bindings.ron";
+const STICK_DEADZONE: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum InputAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Pause,
+    /// Picks the first, second or third upgrade on offer.
+    Pick1,
+    Pick2,
+    Pick3,
+    /// Watches the run that just ended from the game over screen.
+    WatchReplay,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 10] = [
+        InputAction::Up,
+        InputAction::Down,
+        InputAction::Left,
+        InputAction::Right,
+        InputAction::Confirm,
+        InputAction::Pause,
+        InputAction::Pick1,
+        InputAction::Pick2,
+        InputAction::Pick3,
+        InputAction::WatchReplay,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputAction::Up => "UP",
+            InputAction::Down => "DOWN",
+            InputAction::Left => "LEFT",
+            InputAction::Right => "RIGHT",
+            InputAction::Confirm => "CONFIRM",
+            InputAction::Pause => "PAUSE",
+            InputAction::Pick1 => "PICK 1",
+            InputAction::Pick2 => "PICK 2",
+            InputAction::Pick3 => "PICK 3",
+            InputAction::WatchReplay => "WATCH REPLAY",
+        }
+    }
+
+    /// Which way the left stick has to be pushed to trigger the action.
+    fn stick(&self) -> Option<(GamepadAxisType, f32)> {
+        match self {
+            InputAction::Up => Some((GamepadAxisType::LeftStickY, 1.)),
+            InputAction::Down => Some((GamepadAxisType::LeftStickY, -1.)),
+            InputAction::Left => Some((GamepadAxisType::LeftStickX, -1.)),
+            InputAction::Right => Some((GamepadAxisType::LeftStickX, 1.)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Binding {
+    pub keys: Vec<KeyCode>,
+    pub buttons: Vec<GamepadButtonType>,
+}
+
+impl Binding {
+    fn new(keys: &[KeyCode], buttons: &[GamepadButtonType]) -> Self {
+        Self {
+            keys: keys.to_vec(),
+            buttons: buttons.to_vec(),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        self.keys
+            .iter()
+            .map(|key| format!("{:?}", key))
+            .chain(self.buttons.iter().map(|button| format!("{:?}", button)))
+            .collect::<Vec<_>>()
+            .join(", ")
+            .to_uppercase()
+    }
+}
+
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct InputBindings(pub HashMap<InputAction, Binding>);
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use GamepadButtonType::*;
+
+        Self(HashMap::from([
+            (
+                InputAction::Up,
+                Binding::new(&[KeyCode::W, KeyCode::Up], &[DPadUp]),
+            ),
+            (
+                InputAction::Down,
+                Binding::new(&[KeyCode::S, KeyCode::Down], &[DPadDown]),
+            ),
+            (
+                InputAction::Left,
+                Binding::new(&[KeyCode::A, KeyCode::Left], &[DPadLeft]),
+            ),
+            (
+                InputAction::Right,
+                Binding::new(&[KeyCode::D, KeyCode::Right], &[DPadRight]),
+            ),
+            (
+                InputAction::Confirm,
+                Binding::new(&[KeyCode::Space, KeyCode::Return], &[South]),
+            ),
+            (
+                InputAction::Pause,
+                Binding::new(&[KeyCode::Escape, KeyCode::P], &[Start]),
+            ),
+            (
+                InputAction::Pick1,
+                Binding::new(&[KeyCode::Key1, KeyCode::Numpad1], &[West]),
+            ),
+            (
+                InputAction::Pick2,
+                Binding::new(&[KeyCode::Key2, KeyCode::Numpad2], &[North]),
+            ),
+            (
+                InputAction::Pick3,
+                Binding::new(&[KeyCode::Key3, KeyCode::Numpad3], &[East]),
+            ),
+            (
+                InputAction::WatchReplay,
+                Binding::new(&[KeyCode::R], &[Select]),
+            ),
+        ]))
+    }
+}
+
+impl InputBindings {
+    pub fn load() -> Result<Self, String> {
+        let mut bindings: Self = match storage::load(BINDINGS_KEY)? {
+            Some(text) => ron::from_str(&text).map_err(|error| error.to_string())?,
+            None => return Ok(Self::default()),
+        };
+
+        // Actions added since the bindings were saved keep their defaults
+        for (action, binding) in Self::default().0 {
+            bindings.0.entry(action).or_insert(binding);
+        }
+
+        Ok(bindings)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let text = ron::to_string(self).map_err(|error| error.to_string())?;
+
+        storage::save(BINDINGS_KEY, &text)
+    }
+
+    pub fn get(&self, action: InputAction) -> Binding {
+        self.0.get(&action).cloned().unwrap_or_default()
+    }
+
+    /// Binds the key to the action. An action that already had the key gets
+    /// the action's old keys instead, so no key triggers two actions.
+    pub fn bind_key(&mut self, action: InputAction, key: KeyCode) {
+        let previous = std::mem::replace(&mut self.0.entry(action).or_default().keys, vec![key]);
+
+        for (other, binding) in self.0.iter_mut() {
+            if *other != action {
+                swap_input(&mut binding.keys, key, &previous);
+            }
+        }
+    }
+
+    pub fn bind_button(&mut self, action: InputAction, button: GamepadButtonType) {
+        let previous =
+            std::mem::replace(&mut self.0.entry(action).or_default().buttons, vec![button]);
+
+        for (other, binding) in self.0.iter_mut() {
+            if *other != action {
+                swap_input(&mut binding.buttons, button, &previous);
+            }
+        }
+    }
+}
+
+fn swap_input<T: Copy + PartialEq>(inputs: &mut Vec<T>, taken: T, replacements: &[T]) {
+    if !inputs.contains(&taken) {
+        return;
+    }
+
+    inputs.retain(|input| *input != taken);
+    inputs.extend(replacements.iter().filter(|input| **input != taken));
+}
+
+/// The actions held down this frame, computed from the keyboard and every
+/// connected gamepad so gameplay code never looks at raw keys.
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: HashSet<InputAction>,
+    just_pressed: HashSet<InputAction>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.just_pressed.contains(&action)
+    }
+}
+
+pub struct InputActionPlugin;
+
+impl Plugin for InputActionPlugin {
+    fn build(&self, app: &mut App) {
+        let bindings = InputBindings::load().unwrap_or_else(|error| {
+            error!("Could not load input bindings: {}", error);
+            InputBindings::default()
+        });
+
+        app.insert_resource(bindings)
+            .init_resource::<ActionState>()
+            .add_system_to_stage(CoreStage::PreUpdate, action_state_system.after(InputSystem));
+    }
+}
+
+fn action_state_system(
+    bindings: Res<InputBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut action_state: ResMut<ActionState>,
+) {
+    let previous = std::mem::take(&mut action_state.pressed);
+    action_state.just_pressed.clear();
+
+    for action in InputAction::ALL {
+        let binding = bindings.get(action);
+
+        let stick = action.stick().map_or(false, |(axis, sign)| {
+            gamepads.iter().any(|gamepad| {
+                axes.get(GamepadAxis::new(gamepad, axis))
+                    .map_or(false, |value| value * sign > STICK_DEADZONE)
+            })
+        });
+
+        let button_pressed = |button: &GamepadButtonType| {
+            gamepads
+                .iter()
+                .any(|gamepad| gamepad_input.pressed(GamepadButton::new(gamepad, *button)))
+        };
+
+        let button_just_pressed = |button: &GamepadButtonType| {
+            gamepads
+                .iter()
+                .any(|gamepad| gamepad_input.just_pressed(GamepadButton::new(gamepad, *button)))
+        };
+
+        let pressed = stick
+            || keyboard_input.any_pressed(binding.keys.iter().copied())
+            || binding.buttons.iter().any(button_pressed);
+
+        let just_pressed = (stick && !previous.contains(&action))
+            || keyboard_input.any_just_pressed(binding.keys.iter().copied())
+            || binding.buttons.iter().any(button_just_pressed);
+
+        if pressed {
+            action_state.pressed.insert(action);
+        }
+
+        if just_pressed {
+            action_state.just_pressed.insert(action);
+        }
+    }
+}
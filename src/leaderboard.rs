@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error_handler, game::Game, replay::Playback, simulation::difficulty::Difficulty, storage,
+    GameState, UiAssets,
+};
+
+const LEADERBOARD_KEY: &str = "leaderboard.ron";
+const LEADERBOARD_SIZE: usize = 10;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: i32,
+    /// Seconds since the unix epoch.
+    pub date: u64,
+    pub seed: u64,
+    pub duration: Duration,
+    pub enemies_eaten: u32,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+}
+
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Leaderboard {
+    pub entries: Vec<HighScore>,
+}
+
+impl Leaderboard {
+    pub fn load() -> Result<Self, String> {
+        match storage::load(LEADERBOARD_KEY)? {
+            Some(text) => ron::from_str(&text).map_err(|error| error.to_string()),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let text = ron::to_string(self).map_err(|error| error.to_string())?;
+
+        storage::save(LEADERBOARD_KEY, &text)
+    }
+
+    /// Inserts the entry if it makes the table and returns the rank it got.
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|existing| entry.score > existing.score)
+            .unwrap_or(self.entries.len());
+
+        if rank >= LEADERBOARD_SIZE {
+            return None;
+        }
+
+        self.entries.insert(rank, entry);
+        self.entries.truncate(LEADERBOARD_SIZE);
+
+        Some(rank)
+    }
+}
+
+/// The rank of the run that just ended, if it made the leaderboard.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct NewHighScore(pub Option<usize>);
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        let leaderboard = Leaderboard::load().unwrap_or_else(|error| {
+            error!("Could not load the leaderboard: {}", error);
+            Leaderboard::default()
+        });
+
+        app.insert_resource(leaderboard)
+            .init_resource::<NewHighScore>()
+            .add_enter_system(
+                GameState::GameOver,
+                record_high_score_system
+                    .chain(error_handler)
+                    .label("record_high_score"),
+            );
+    }
+}
+
+fn record_high_score_system(
+    game: Res<Game>,
+    playback: Option<Res<Playback>>,
+    mut leaderboard: ResMut<Leaderboard>,
+    mut new_high_score: ResMut<NewHighScore>,
+) -> Result<(), String> {
+    new_high_score.0 = None;
+
+    if playback.is_some() {
+        return Ok(());
+    }
+
+    new_high_score.0 = leaderboard.insert(HighScore {
+        score: game.score(),
+        date: now(),
+        seed: game.seed(),
+        duration: game.elapsed(),
+        enemies_eaten: game.enemies_eaten,
+        difficulty: game.config().difficulty,
+    });
+
+    if new_high_score.is_none() {
+        return Ok(());
+    }
+
+    leaderboard.save()
+}
+
+pub fn spawn_leaderboard(
+    parent: &mut ChildBuilder,
+    leaderboard: &Leaderboard,
+    new_high_score: &NewHighScore,
+    ui_assets: &UiAssets,
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                margin: UiRect {
+                    top: Val::Px(10.),
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            for (rank, entry) in leaderboard.entries.iter().enumerate() {
+                let color = match new_high_score.0 == Some(rank) {
+                    true => Color::YELLOW,
+                    false => Color::WHITE,
+                };
+
+                parent.spawn(TextBundle::from_section(
+                    format!(
+                        "{:>2}. {:>4}   {:<6}   {}   {:>2}:{:02}   {:>3} EATEN   SEED {}",
+                        rank + 1,
+                        entry.score,
+                        entry.difficulty.name(),
+                        format_date(entry.date),
+                        entry.duration.as_secs() / 60,
+                        entry.duration.as_secs() % 60,
+                        entry.enemies_eaten,
+                        entry.seed,
+                    ),
+                    TextStyle {
+                        font: ui_assets.font.clone(),
+                        font_size: 20.,
+                        color,
+                    },
+                ));
+            }
+        });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now() -> u64 {
+    (js_sys::Date::now() / 1000.) as u64
+}
+
+/// Formats a unix timestamp as `YYYY-MM-DD`, using Howard Hinnant's
+/// `civil_from_days` so we don't need a date crate for one column.
+fn format_date(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -1,25 +1,78 @@
-use bevy::prelude::*;
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+};
 use iyes_loopless::prelude::*;
 
-use crate::{despawn, GameState, TextureAssets};
+use crate::{
+    despawn,
+    simulation::{grid::Tile, level::Level, SimulationEvent},
+    DataAssets, GameState, Position, TextureAssets,
+};
 
-pub const LEVEL_SIZE: IVec2 = IVec2::new(15, 11);
+#[derive(Component)]
+struct LevelTile;
 
 #[derive(Component)]
-struct Level;
+struct CrateTile(Position);
+
+/// A level read from a `.level.ron` file, checked while it loads so a broken
+/// layout never makes it into a run.
+#[derive(TypeUuid, Deref)]
+#[uuid = "53d48c95-5310-4ace-9f93-25759780668e"]
+pub struct LevelAsset(pub Level);
+
+#[derive(Default)]
+struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let level = ron::de::from_bytes::<Level>(bytes)?;
+
+            load_context.set_default_asset(LoadedAsset::new(LevelAsset(level)));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
 
 pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
-        app.add_enter_system(GameState::Playing, level_setup_system)
-            .add_exit_system(GameState::Playing, despawn::<Level>);
+        app.add_asset::<LevelAsset>()
+            .init_asset_loader::<LevelLoader>()
+            .add_enter_system(GameState::Playing, level_setup_system)
+            .add_system(crate_broken_system.run_in_state(GameState::Playing))
+            .add_exit_system(GameState::Playing, despawn::<LevelTile>);
     }
 }
 
-fn level_setup_system(mut commands: Commands, assets: Res<TextureAssets>) {
-    for i in -LEVEL_SIZE.x..=LEVEL_SIZE.x {
-        for j in -LEVEL_SIZE.y..=LEVEL_SIZE.y {
+fn level_setup_system(
+    mut commands: Commands,
+    assets: Res<TextureAssets>,
+    data_assets: Res<DataAssets>,
+    levels: Res<Assets<LevelAsset>>,
+) {
+    let default_level = Level::default();
+    let level = levels
+        .get(&data_assets.level)
+        .map_or(&default_level, |level| &level.0);
+    let grid = &level.grid;
+    let (min, max) = (grid.min(), grid.max());
+
+    for i in min.x..=max.x {
+        for j in min.y..=max.y {
             let texture = assets.tile_light.clone();
 
             commands.spawn((
@@ -35,7 +88,7 @@ fn level_setup_system(mut commands: Commands, assets: Res<TextureAssets>) {
                     },
                     ..default()
                 },
-                Level,
+                LevelTile,
             ));
         }
     }
@@ -43,11 +96,7 @@ fn level_setup_system(mut commands: Commands, assets: Res<TextureAssets>) {
     commands.spawn((
         SpriteSheetBundle {
             texture_atlas: assets.wall_sheet.clone(),
-            transform: Transform::from_xyz(
-                -LEVEL_SIZE.x as f32 - 1.0,
-                LEVEL_SIZE.y as f32 + 1.0,
-                1.0,
-            ),
+            transform: Transform::from_xyz(min.x as f32 - 1.0, max.y as f32 + 1.0, 1.0),
             sprite: TextureAtlasSprite {
                 index: 0,
                 custom_size: Some(Vec2::new(1.0, 1.0)),
@@ -55,16 +104,12 @@ fn level_setup_system(mut commands: Commands, assets: Res<TextureAssets>) {
             },
             ..default()
         },
-        Level,
+        LevelTile,
     ));
     commands.spawn((
         SpriteSheetBundle {
             texture_atlas: assets.wall_sheet.clone(),
-            transform: Transform::from_xyz(
-                LEVEL_SIZE.x as f32 + 1.0,
-                LEVEL_SIZE.y as f32 + 1.0,
-                1.0,
-            ),
+            transform: Transform::from_xyz(max.x as f32 + 1.0, max.y as f32 + 1.0, 1.0),
             sprite: TextureAtlasSprite {
                 index: 2,
                 custom_size: Some(Vec2::new(1.0, 1.0)),
@@ -72,16 +117,12 @@ fn level_setup_system(mut commands: Commands, assets: Res<TextureAssets>) {
             },
             ..default()
         },
-        Level,
+        LevelTile,
     ));
     commands.spawn((
         SpriteSheetBundle {
             texture_atlas: assets.wall_sheet.clone(),
-            transform: Transform::from_xyz(
-                -LEVEL_SIZE.x as f32 - 1.0,
-                -LEVEL_SIZE.y as f32 - 1.0,
-                1.0,
-            ),
+            transform: Transform::from_xyz(min.x as f32 - 1.0, min.y as f32 - 1.0, 1.0),
             sprite: TextureAtlasSprite {
                 index: 4,
                 custom_size: Some(Vec2::new(1.0, 1.0)),
@@ -89,16 +130,12 @@ fn level_setup_system(mut commands: Commands, assets: Res<TextureAssets>) {
             },
             ..default()
         },
-        Level,
+        LevelTile,
     ));
     commands.spawn((
         SpriteSheetBundle {
             texture_atlas: assets.wall_sheet.clone(),
-            transform: Transform::from_xyz(
-                LEVEL_SIZE.x as f32 + 1.0,
-                -LEVEL_SIZE.y as f32 - 1.0,
-                1.0,
-            ),
+            transform: Transform::from_xyz(max.x as f32 + 1.0, min.y as f32 - 1.0, 1.0),
             sprite: TextureAtlasSprite {
                 index: 5,
                 custom_size: Some(Vec2::new(1.0, 1.0)),
@@ -106,14 +143,14 @@ fn level_setup_system(mut commands: Commands, assets: Res<TextureAssets>) {
             },
             ..default()
         },
-        Level,
+        LevelTile,
     ));
 
     for i in 0..2 {
-        for j in -LEVEL_SIZE.y..=LEVEL_SIZE.y {
+        for j in min.y..=max.y {
             let x = match i {
-                0 => LEVEL_SIZE.x as f32 + 1.0,
-                1 => -LEVEL_SIZE.x as f32 - 1.0,
+                0 => max.x as f32 + 1.0,
+                1 => min.x as f32 - 1.0,
                 _ => unreachable!(),
             };
 
@@ -130,18 +167,18 @@ fn level_setup_system(mut commands: Commands, assets: Res<TextureAssets>) {
                     },
                     ..default()
                 },
-                Level,
+                LevelTile,
             ));
         }
     }
 
     for i in 0..2 {
-        for j in -LEVEL_SIZE.x..=LEVEL_SIZE.x {
+        for j in min.x..=max.x {
             let x = j as f32;
 
             let y = match i {
-                0 => LEVEL_SIZE.y as f32 + 1.0,
-                1 => -LEVEL_SIZE.y as f32 - 1.0,
+                0 => max.y as f32 + 1.0,
+                1 => min.y as f32 - 1.0,
                 _ => unreachable!(),
             };
 
@@ -156,8 +193,93 @@ fn level_setup_system(mut commands: Commands, assets: Res<TextureAssets>) {
                     },
                     ..default()
                 },
-                Level,
+                LevelTile,
             ));
         }
     }
+
+    for position in grid.positions() {
+        let translation = Vec3::new(position.x as f32, position.y as f32, 1.2);
+
+        match grid.tile(&position) {
+            Some(Tile::Wall) => {
+                // Walls in a column use the vertical piece, anything else the
+                // horizontal one
+                let above = grid.tile(&Position {
+                    y: position.y + 1,
+                    ..position
+                }) == Some(Tile::Wall);
+                let below = grid.tile(&Position {
+                    y: position.y - 1,
+                    ..position
+                }) == Some(Tile::Wall);
+                let index = match above || below {
+                    true => 3,
+                    false => 1,
+                };
+
+                commands.spawn((
+                    SpriteSheetBundle {
+                        texture_atlas: assets.wall_sheet.clone(),
+                        transform: Transform::from_translation(translation),
+                        sprite: TextureAtlasSprite {
+                            index,
+                            custom_size: Some(Vec2::new(1.0, 1.0)),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    LevelTile,
+                ));
+            }
+            Some(Tile::Pit) => {
+                commands.spawn((
+                    SpriteBundle {
+                        transform: Transform::from_translation(translation),
+                        sprite: Sprite {
+                            color: Color::rgb(0.05, 0.05, 0.08),
+                            custom_size: Some(Vec2::new(1.0, 1.0)),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    LevelTile,
+                ));
+            }
+            Some(Tile::Crate) => {
+                commands.spawn((
+                    SpriteSheetBundle {
+                        texture_atlas: assets.wall_sheet.clone(),
+                        transform: Transform::from_translation(translation),
+                        sprite: TextureAtlasSprite {
+                            index: 0,
+                            color: Color::rgb(0.75, 0.5, 0.25),
+                            custom_size: Some(Vec2::new(1.0, 1.0)),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    CrateTile(position),
+                    LevelTile,
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn crate_broken_system(
+    mut commands: Commands,
+    mut events: EventReader<SimulationEvent>,
+    crate_query: Query<(Entity, &CrateTile)>,
+) {
+    for event in events.iter() {
+        if let SimulationEvent::CrateBroken { position } = event {
+            for (entity, crate_tile) in crate_query.iter() {
+                if crate_tile.0 == *position {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+        }
+    }
 }
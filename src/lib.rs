@@ -1,16 +1,31 @@
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 use bevy_kira_audio::prelude::*;
-use level::LEVEL_SIZE;
 
+pub mod boss;
+pub mod controls;
+pub mod difficulty;
 pub mod enemy;
 pub mod game;
+pub mod input;
+pub mod leaderboard;
 pub mod level;
 pub mod menu;
 pub mod music;
+pub mod pause;
+pub mod pickup;
+pub mod replay;
 pub mod score;
+pub mod seed;
+pub mod settings;
+pub mod simulation;
 pub mod snake;
 pub mod splash;
+pub mod storage;
+pub mod upgrade;
+pub mod waves;
+
+pub use simulation::Position;
 
 pub const SCALE: i32 = 32;
 
@@ -23,34 +38,6 @@ pub enum GameState {
     GameOver,
 }
 
-#[derive(Component, Clone, Copy, Eq, PartialEq, Debug)]
-pub struct Position {
-    pub x: i32,
-    pub y: i32,
-}
-
-impl Position {
-    pub fn as_tuple(&self) -> (i32, i32) {
-        (self.x, self.y)
-    }
-
-    pub fn in_world(&self) -> bool {
-        self.x >= -LEVEL_SIZE.x
-            && self.x <= LEVEL_SIZE.x
-            && self.y >= -LEVEL_SIZE.y
-            && self.y <= LEVEL_SIZE.y
-    }
-}
-
-impl From<Vec2> for Position {
-    fn from(vec: Vec2) -> Self {
-        Self {
-            x: vec.x.floor() as i32,
-            y: vec.y.floor() as i32,
-        }
-    }
-}
-
 #[derive(Component, Deref, DerefMut)]
 pub struct DestroyAfter(Timer);
 
@@ -73,14 +60,8 @@ pub struct TextureAssets {
     #[asset(path = "sprites/effect.png")]
     pub effect: Handle<Image>,
 
-    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 16., columns = 3, rows = 1))]
-    #[asset(path = "sprites/wizard_sheet.png")]
-    pub wizard_sheet: Handle<TextureAtlas>,
     #[asset(path = "sprites/projectile.png")]
     pub projectile: Handle<Image>,
-    #[asset(texture_atlas(tile_size_x = 16., tile_size_y = 16., columns = 3, rows = 1))]
-    #[asset(path = "sprites/knight_sheet.png")]
-    pub knight_sheet: Handle<TextureAtlas>,
 }
 
 #[derive(AssetCollection, Resource)]
@@ -90,13 +71,6 @@ pub struct AudioAssets {
     #[asset(path = "music/menu.wav")]
     pub menu_music: Handle<AudioSource>,
 
-    #[asset(path = "sounds/wizard_prepare.ogg")]
-    pub wizard_prepare: Handle<AudioSource>,
-    #[asset(path = "sounds/wizard_attack.ogg")]
-    pub wizard_attack: Handle<AudioSource>,
-    #[asset(path = "sounds/knight_attack.wav")]
-    pub knight_attack: Handle<AudioSource>,
-
     #[asset(path = "sounds/hit.ogg")]
     pub hit: Handle<AudioSource>,
     #[asset(path = "sounds/death_by_bumping.ogg")]
@@ -105,8 +79,33 @@ pub struct AudioAssets {
     pub eat: Handle<AudioSource>,
 }
 
+#[derive(AssetCollection, Resource)]
+pub struct DataAssets {
+    #[asset(path = "data/default.enemies.ron")]
+    pub enemies: Handle<enemy::EnemyRoster>,
+    #[asset(path = "data/arena.level.ron")]
+    pub level: Handle<level::LevelAsset>,
+    #[asset(path = "data/default.waves.ron")]
+    pub waves: Handle<waves::WavesAsset>,
+    #[asset(path = "data/default.upgrades.ron")]
+    pub upgrades: Handle<upgrade::UpgradeRegistry>,
+    #[asset(path = "data/default.pickups.ron")]
+    pub pickups: Handle<pickup::PickupsAsset>,
+    #[asset(path = "data/easy.difficulty.ron")]
+    pub easy: Handle<difficulty::DifficultyAsset>,
+    #[asset(path = "data/normal.difficulty.ron")]
+    pub normal: Handle<difficulty::DifficultyAsset>,
+    #[asset(path = "data/hard.difficulty.ron")]
+    pub hard: Handle<difficulty::DifficultyAsset>,
+    #[asset(path = "data/custom.difficulty.ron")]
+    pub custom: Handle<difficulty::DifficultyAsset>,
+}
+
 #[derive(AssetCollection, Resource)]
 pub struct UiAssets {
+    #[asset(path = "fonts/impact.ttf")]
+    pub font: Handle<Font>,
+
     #[asset(path = "ui/logo.png")]
     pub logo: Handle<Image>,
 
@@ -159,6 +158,26 @@ pub fn despawn_after(
     }
 }
 
+/// Looks up `--name value` or `--name=value` in the command line arguments.
+pub fn cli_arg(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+
+        if let Some(value) = arg
+            .strip_prefix(name)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Some(value.to_string());
+        }
+    }
+
+    None
+}
+
 // TODO: Anyhow for errors
 pub fn error_handler(In(result): In<Result<(), String>>) {
     if let Err(error) = result {
@@ -1,33 +1,41 @@
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
-use bevy_framepace::{FramepacePlugin, FramepaceSettings, Limiter};
+use bevy_framepace::{FramepacePlugin, FramepaceSettings};
 use bevy_kira_audio::prelude::*;
 use bevy_pixel_camera::{PixelCameraBundle, PixelCameraPlugin};
 use iyes_loopless::prelude::*;
 use snake_survivors::{
-    despawn_after, game::GamePlugin, AudioAssets, GameState, TextureAssets, UiAssets, SCALE,
+    despawn_after, game::GamePlugin, pause::PauseState, settings::Settings, AudioAssets,
+    DataAssets, GameState, TextureAssets, UiAssets,
 };
 
 fn main() {
     let mut app = App::new();
 
+    // Settings pick the window mode, so they load before logging is up
+    let (settings, settings_error) = match Settings::load() {
+        Ok(settings) => (settings, None),
+        Err(error) => (Settings::default(), Some(error)),
+    };
+
     app.add_loopless_state(GameState::AssetsLoading)
         .add_loading_state(
             LoadingState::new(GameState::AssetsLoading)
                 .continue_to_state(GameState::SplashScreen)
                 .with_collection::<TextureAssets>()
                 .with_collection::<AudioAssets>()
+                .with_collection::<DataAssets>()
                 .with_collection::<UiAssets>(),
         )
         .add_startup_system(setup_system)
-        .add_system(despawn_after)
+        .add_system(despawn_after.run_in_state(PauseState::Running))
         .add_plugins(
             DefaultPlugins
                 .set(ImagePlugin::default_nearest())
                 .set(WindowPlugin {
                     window: WindowDescriptor {
                         fit_canvas_to_parent: true,
-                        mode: WindowMode::BorderlessFullscreen,
+                        mode: settings.display_mode.into(),
                         ..default()
                     },
                     ..default()
@@ -36,15 +44,24 @@ fn main() {
         .add_plugin(FramepacePlugin)
         .add_plugin(AudioPlugin)
         .add_plugin(PixelCameraPlugin)
-        .add_plugin(GamePlugin);
+        .add_plugin(GamePlugin)
+        .insert_resource(settings);
+
+    if let Some(error) = settings_error {
+        error!("Could not load settings: {}", error);
+    }
 
     app.run()
 }
 
-fn setup_system(mut commands: Commands, mut settings: ResMut<FramepaceSettings>) {
-    settings.limiter = Limiter::from_framerate(60.);
+fn setup_system(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut framepace: ResMut<FramepaceSettings>,
+) {
+    framepace.limiter = settings.limiter();
 
-    let camera = PixelCameraBundle::from_zoom(SCALE);
+    let camera = PixelCameraBundle::from_zoom(settings.zoom);
 
     commands.spawn(camera);
 }
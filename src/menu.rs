@@ -1,12 +1,19 @@
 use bevy::prelude::*;
 use iyes_loopless::prelude::*;
 
-use crate::{despawn, GameState, UiAssets};
+use crate::{
+    despawn,
+    input::{ActionState, InputAction},
+    seed::{seed_text_bundle, Seed},
+    GameState, UiAssets,
+};
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
-enum MenuState {
+pub enum MenuState {
     Disabled,
     Main,
+    Settings,
+    Controls,
 }
 
 #[derive(Component)]
@@ -15,6 +22,12 @@ pub struct PlayButton;
 #[derive(Component)]
 pub struct ExitButton;
 
+#[derive(Component)]
+struct SettingsButton;
+
+#[derive(Component)]
+struct ControlsButton;
+
 #[derive(Component)]
 struct OnMenu;
 
@@ -29,6 +42,9 @@ impl Plugin for MenuPlugin {
                 ConditionSet::new()
                     .run_in_state(MenuState::Main)
                     .with_system(button_play.run_if(button_interacted::<PlayButton>))
+                    .with_system(button_play.run_if(action_just_pressed(InputAction::Confirm)))
+                    .with_system(button_settings.run_if(button_interacted::<SettingsButton>))
+                    .with_system(button_controls.run_if(button_interacted::<ControlsButton>))
                     .with_system(button_exit.run_if(button_interacted::<ExitButton>))
                     .into(),
             )
@@ -40,7 +56,7 @@ fn menu_setup_system(mut commands: Commands) {
     commands.insert_resource(NextState(MenuState::Main));
 }
 
-fn main_menu_setup_system(mut commands: Commands, ui_assets: Res<UiAssets>) {
+fn main_menu_setup_system(mut commands: Commands, ui_assets: Res<UiAssets>, seed: Res<Seed>) {
     commands
         .spawn((
             NodeBundle {
@@ -85,6 +101,9 @@ fn main_menu_setup_system(mut commands: Commands, ui_assets: Res<UiAssets>) {
                 PlayButton,
             ));
 
+            spawn_text_button(parent, "SETTINGS", &ui_assets, SettingsButton);
+            spawn_text_button(parent, "CONTROLS", &ui_assets, ControlsButton);
+
             #[cfg(not(target_arch = "wasm32"))]
             parent.spawn((
                 ButtonBundle {
@@ -103,6 +122,61 @@ fn main_menu_setup_system(mut commands: Commands, ui_assets: Res<UiAssets>) {
                 },
                 ExitButton,
             ));
+
+            parent.spawn(seed_text_bundle(&seed, &ui_assets));
+        });
+}
+
+pub fn image_button(image: Handle<Image>, top: f32) -> ButtonBundle {
+    ButtonBundle {
+        style: Style {
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            size: Size::new(Val::Px(200.), Val::Px(80.)),
+            margin: UiRect {
+                top: Val::Px(top),
+                ..default()
+            },
+            ..default()
+        },
+        image: image.into(),
+        ..default()
+    }
+}
+
+pub fn spawn_text_button(
+    parent: &mut ChildBuilder,
+    label: &str,
+    ui_assets: &UiAssets,
+    bundle: impl Bundle,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    size: Size::new(Val::Px(200.), Val::Px(80.)),
+                    margin: UiRect {
+                        top: Val::Px(20.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                ..default()
+            },
+            bundle,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font: ui_assets.font.clone(),
+                    font_size: 40.,
+                    color: Color::WHITE,
+                },
+            ));
         });
 }
 
@@ -114,11 +188,23 @@ pub fn button_interacted<T: Component>(
         .any(|interaction| *interaction == Interaction::Clicked)
 }
 
+pub fn action_just_pressed(action: InputAction) -> impl Fn(Res<ActionState>) -> bool {
+    move |action_state: Res<ActionState>| action_state.just_pressed(action)
+}
+
 pub fn button_play(mut commands: Commands) {
     commands.insert_resource(NextState(MenuState::Disabled));
     commands.insert_resource(NextState(GameState::Playing));
 }
 
+fn button_settings(mut commands: Commands) {
+    commands.insert_resource(NextState(MenuState::Settings));
+}
+
+fn button_controls(mut commands: Commands) {
+    commands.insert_resource(NextState(MenuState::Controls));
+}
+
 pub fn button_exit(mut app_exit_events: ResMut<Events<bevy::app::AppExit>>) {
     app_exit_events.send(bevy::app::AppExit);
 }
@@ -1,9 +1,137 @@
-use crate::{AudioAssets, GameState};
+use crate::{pause::PauseState, storage, AudioAssets, GameState};
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 use iyes_loopless::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+const MIXER_KEY: &str = "mixer.ron";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bus {
+    Music,
+    Effects,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sound {
+    MenuMusic,
+    GameplayMusic,
+    Hit,
+    DeathByBumping,
+    Eat,
+}
+
+/// Groups of sounds that come from data files instead of `AudioAssets`, so
+/// they can be added without touching the mix table.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SoundCategory {
+    Enemies,
+}
+
+impl SoundCategory {
+    fn mix(&self) -> (Bus, f64) {
+        match self {
+            SoundCategory::Enemies => (Bus::Effects, 1.),
+        }
+    }
+}
+
+impl Sound {
+    /// The bus each sound is mixed into and its gain before the bus levels
+    /// are applied, so the sounds stay balanced against each other.
+    fn mix(&self) -> (Bus, f64) {
+        match self {
+            Sound::MenuMusic => (Bus::Music, 0.75),
+            Sound::GameplayMusic => (Bus::Music, 0.33),
+            Sound::Hit => (Bus::Effects, 1.),
+            Sound::DeathByBumping => (Bus::Effects, 1.),
+            Sound::Eat => (Bus::Effects, 0.5),
+        }
+    }
+
+    pub fn handle(&self, assets: &AudioAssets) -> Handle<AudioSource> {
+        match self {
+            Sound::MenuMusic => assets.menu_music.clone(),
+            Sound::GameplayMusic => assets.gameplay_music.clone(),
+            Sound::Hit => assets.hit.clone(),
+            Sound::DeathByBumping => assets.death_by_bumping.clone(),
+            Sound::Eat => assets.eat.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Fader {
+    pub volume: f64,
+    pub muted: bool,
+}
+
+impl Default for Fader {
+    fn default() -> Self {
+        Self {
+            volume: 1.,
+            muted: false,
+        }
+    }
+}
+
+impl Fader {
+    fn gain(&self) -> f64 {
+        match self.muted {
+            true => 0.,
+            false => self.volume,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Mixer {
+    pub master: Fader,
+    pub music: Fader,
+    pub effects: Fader,
+}
+
+impl Mixer {
+    pub fn load() -> Result<Self, String> {
+        match storage::load(MIXER_KEY)? {
+            Some(text) => ron::from_str(&text).map_err(|error| error.to_string()),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let text = ron::to_string(self).map_err(|error| error.to_string())?;
+
+        storage::save(MIXER_KEY, &text)
+    }
+
+    /// The volume to play a sound at with the current levels.
+    pub fn gain(&self, sound: Sound) -> f64 {
+        let (bus, base) = sound.mix();
+
+        base * self.bus_gain(bus)
+    }
+
+    /// The volume for a sound of the category, before the file's own gain.
+    pub fn category_gain(&self, category: SoundCategory) -> f64 {
+        let (bus, base) = category.mix();
+
+        base * self.bus_gain(bus)
+    }
+
+    /// The volume of a bus after the master fader.
+    fn bus_gain(&self, bus: Bus) -> f64 {
+        let fader = match bus {
+            Bus::Music => self.music,
+            Bus::Effects => self.effects,
+        };
+
+        fader.gain() * self.master.gain()
+    }
+}
+
 #[derive(Resource)]
 pub struct Gameplay;
 
@@ -17,26 +145,54 @@ pub struct MusicPlugin;
 
 impl Plugin for MusicPlugin {
     fn build(&self, app: &mut App) {
-        app.add_audio_channel::<Menu>()
+        let mixer = Mixer::load().unwrap_or_else(|error| {
+            error!("Could not load the mixer levels: {}", error);
+            Mixer::default()
+        });
+
+        app.insert_resource(mixer)
+            .add_audio_channel::<Menu>()
             .add_audio_channel::<Background>()
             .add_audio_channel::<Gameplay>()
+            .add_system(volume_system)
             .add_enter_system(GameState::Menu, play_menu_music_system)
             .add_exit_system(GameState::Menu, stop_menu_music_system)
             .add_enter_system(GameState::GameOver, play_menu_music_system)
             .add_exit_system(GameState::GameOver, stop_menu_music_system)
             .add_enter_system(GameState::Playing, play_music_system)
-            .add_exit_system(GameState::Playing, stop_music_system);
+            .add_exit_system(GameState::Playing, stop_music_system)
+            .add_enter_system(PauseState::Paused, pause_music_system)
+            .add_exit_system(PauseState::Paused, resume_music_system);
+    }
+}
+
+/// Effects are short enough to pick up new levels on their next play, but
+/// the music loops, so it has to be turned up or down while it plays.
+fn volume_system(
+    mixer: Res<Mixer>,
+    menu: Res<AudioChannel<Menu>>,
+    background: Res<AudioChannel<Background>>,
+) {
+    if !mixer.is_changed() {
+        return;
     }
+
+    menu.set_volume(mixer.gain(Sound::MenuMusic));
+    background.set_volume(mixer.gain(Sound::GameplayMusic));
 }
 
-fn play_music_system(assets: Res<AudioAssets>, background: Res<AudioChannel<Background>>) {
+fn play_music_system(
+    assets: Res<AudioAssets>,
+    mixer: Res<Mixer>,
+    background: Res<AudioChannel<Background>>,
+) {
     background
-        .play(assets.gameplay_music.clone())
+        .play(Sound::GameplayMusic.handle(&assets))
         .fade_in(AudioTween::new(
             Duration::from_secs_f32(1.5),
             AudioEasing::OutPowi(1),
         ))
-        .with_volume(0.33)
+        .with_volume(mixer.gain(Sound::GameplayMusic))
         .looped();
 }
 
@@ -47,9 +203,21 @@ fn stop_music_system(background: Res<AudioChannel<Background>>) {
     ));
 }
 
-fn play_menu_music_system(assets: Res<AudioAssets>, menu: Res<AudioChannel<Menu>>) {
-    menu.play(assets.menu_music.clone())
-        .with_volume(0.75)
+fn pause_music_system(background: Res<AudioChannel<Background>>) {
+    background.pause();
+}
+
+fn resume_music_system(background: Res<AudioChannel<Background>>) {
+    background.resume();
+}
+
+fn play_menu_music_system(
+    assets: Res<AudioAssets>,
+    mixer: Res<Mixer>,
+    menu: Res<AudioChannel<Menu>>,
+) {
+    menu.play(Sound::MenuMusic.handle(&assets))
+        .with_volume(mixer.gain(Sound::MenuMusic))
         .looped();
 }
 
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{
+    despawn,
+    input::{ActionState, InputAction},
+    menu::{button_interacted, image_button, spawn_text_button},
+    replay::Playback,
+    GameState, UiAssets,
+};
+
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub enum PauseState {
+    Running,
+    Paused,
+}
+
+#[derive(Component)]
+struct OnPause;
+
+#[derive(Component)]
+struct ResumeButton;
+
+#[derive(Component)]
+struct RestartButton;
+
+#[derive(Component)]
+struct QuitButton;
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_loopless_state(PauseState::Running)
+            .add_system(toggle_pause_system.run_in_state(GameState::Playing))
+            .add_exit_system(GameState::Playing, resume_system)
+            .add_enter_system(PauseState::Paused, pause_menu_setup_system)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(PauseState::Paused)
+                    .with_system(resume_system.run_if(button_interacted::<ResumeButton>))
+                    .with_system(button_restart.run_if(button_interacted::<RestartButton>))
+                    .with_system(button_quit.run_if(button_interacted::<QuitButton>))
+                    .into(),
+            )
+            .add_exit_system(PauseState::Paused, despawn::<OnPause>);
+    }
+}
+
+fn toggle_pause_system(
+    mut commands: Commands,
+    pause_state: Res<CurrentState<PauseState>>,
+    action_state: Res<ActionState>,
+) {
+    if !action_state.just_pressed(InputAction::Pause) {
+        return;
+    }
+
+    match pause_state.0 {
+        PauseState::Running => commands.insert_resource(NextState(PauseState::Paused)),
+        PauseState::Paused => commands.insert_resource(NextState(PauseState::Running)),
+    }
+}
+
+fn resume_system(mut commands: Commands) {
+    commands.insert_resource(NextState(PauseState::Running));
+}
+
+fn button_restart(mut commands: Commands) {
+    // A replay being watched is rewound by reset_game_system
+    commands.insert_resource(NextState(GameState::Playing));
+}
+
+fn button_quit(mut commands: Commands) {
+    // Otherwise the menu would start the replay straight back up
+    commands.remove_resource::<Playback>();
+    commands.insert_resource(NextState(GameState::Menu));
+}
+
+fn pause_menu_setup_system(mut commands: Commands, ui_assets: Res<UiAssets>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.6).into(),
+                ..default()
+            },
+            OnPause,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                image_button(ui_assets.start_button.clone(), 0.),
+                ResumeButton,
+            ));
+
+            spawn_text_button(parent, "RESTART", &ui_assets, RestartButton);
+
+            parent.spawn((image_button(ui_assets.exit_button.clone(), 20.), QuitButton));
+        });
+}
@@ -0,0 +1,285 @@
+use std::collections::HashSet;
+
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use iyes_loopless::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    despawn,
+    game::Game,
+    simulation::{
+        pickup::{PickupConfig, PickupId, PickupStats},
+        SimulationEvent,
+    },
+    DataAssets, DestroyAfter, GameState, UiAssets,
+};
+
+/// Pickups start blinking once this much of their lifetime is gone.
+const BLINK_AFTER: f32 = 0.75;
+
+#[derive(Deserialize)]
+pub struct PickupDefinition {
+    pub name: String,
+    pub color: (f32, f32, f32),
+    pub stats: PickupStats,
+}
+
+impl PickupDefinition {
+    fn color(&self) -> Color {
+        let (r, g, b) = self.color;
+
+        Color::rgb(r, g, b)
+    }
+}
+
+/// The pickups of a `.pickups.ron` file. The simulation gets the stats in
+/// the same order, so a `PickupKind` indexes straight into `pickups`.
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "9e4b2a71-0c3d-4f58-8a16-d75b3e9c2f04"]
+pub struct PickupsAsset {
+    interval: f32,
+    max_out: usize,
+    min_distance: i32,
+    pub pickups: Vec<PickupDefinition>,
+}
+
+impl PickupsAsset {
+    pub fn config(&self) -> PickupConfig {
+        PickupConfig {
+            interval: self.interval,
+            max_out: self.max_out,
+            min_distance: self.min_distance,
+            kinds: self
+                .pickups
+                .iter()
+                .map(|pickup| pickup.stats.clone())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct PickupsLoader;
+
+impl AssetLoader for PickupsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let pickups = ron::de::from_bytes::<PickupsAsset>(bytes)?;
+
+            load_context.set_default_asset(LoadedAsset::new(pickups));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["pickups.ron"]
+    }
+}
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<PickupsAsset>()
+            .init_asset_loader::<PickupsLoader>()
+            .add_enter_system(GameState::Playing, spawn_boost_display)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Playing)
+                    .with_system(draw_pickups_system)
+                    .with_system(pickup_text_system)
+                    .with_system(boost_display_system)
+                    .into(),
+            )
+            .add_exit_system(GameState::Playing, despawn::<PickupSprite>)
+            .add_exit_system(GameState::Playing, despawn::<PickupText>)
+            .add_exit_system(GameState::Playing, despawn::<BoostDisplay>);
+    }
+}
+
+#[derive(Component)]
+struct PickupSprite(PickupId);
+
+#[derive(Component)]
+struct PickupText;
+
+#[derive(Component)]
+struct BoostDisplay;
+
+fn draw_pickups_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    data_assets: Res<DataAssets>,
+    pickups_assets: Res<Assets<PickupsAsset>>,
+    mut sprite_query: Query<(Entity, &PickupSprite, &mut Visibility)>,
+) {
+    let mut drawn = HashSet::new();
+
+    for (entity, sprite, mut visibility) in sprite_query.iter_mut() {
+        let pickup = match game.pickups.iter().find(|pickup| pickup.id == sprite.0) {
+            Some(pickup) => pickup,
+            None => {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        };
+
+        // Blink once the pickup is about to vanish
+        visibility.is_visible = pickup.age() < BLINK_AFTER || (pickup.age() * 40.).fract() < 0.6;
+        drawn.insert(sprite.0);
+    }
+
+    let pickups_asset = match pickups_assets.get(&data_assets.pickups) {
+        Some(pickups_asset) => pickups_asset,
+        None => return,
+    };
+
+    for pickup in game.pickups.iter() {
+        if drawn.contains(&pickup.id) {
+            continue;
+        }
+
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: pickups_asset.pickups[pickup.kind.0].color(),
+                    custom_size: Some(Vec2::splat(0.6)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(
+                    pickup.position.x as f32,
+                    pickup.position.y as f32,
+                    1.2,
+                ),
+                ..default()
+            },
+            PickupSprite(pickup.id),
+        ));
+    }
+}
+
+fn pickup_text_system(
+    mut commands: Commands,
+    mut events: EventReader<SimulationEvent>,
+    data_assets: Res<DataAssets>,
+    pickups_assets: Res<Assets<PickupsAsset>>,
+    ui_assets: Res<UiAssets>,
+) {
+    let pickups_asset = match pickups_assets.get(&data_assets.pickups) {
+        Some(pickups_asset) => pickups_asset,
+        None => return,
+    };
+
+    for event in events.iter() {
+        let kind = match event {
+            SimulationEvent::PickupCollected { kind, .. } => kind,
+            _ => continue,
+        };
+        let pickup = &pickups_asset.pickups[kind.0];
+
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::FlexStart,
+                        padding: UiRect {
+                            top: Val::Percent(22.),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    ..default()
+                },
+                PickupText,
+                DestroyAfter(Timer::from_seconds(1., TimerMode::Once)),
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    pickup.name.to_uppercase(),
+                    TextStyle {
+                        font: ui_assets.font.clone(),
+                        font_size: 32.,
+                        color: pickup.color(),
+                    },
+                ));
+            });
+    }
+}
+
+fn spawn_boost_display(mut commands: Commands, ui_assets: Res<UiAssets>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    justify_content: JustifyContent::FlexEnd,
+                    align_items: AlignItems::FlexStart,
+                    flex_direction: FlexDirection::Column,
+                    position_type: PositionType::Absolute,
+                    padding: UiRect::all(Val::Px(16.)),
+                    ..default()
+                },
+                ..default()
+            },
+            BoostDisplay,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: ui_assets.font.clone(),
+                    font_size: 24.,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+/// Lists the boosts that are running, with how much of each is left.
+fn boost_display_system(
+    game: Res<Game>,
+    data_assets: Res<DataAssets>,
+    pickups_assets: Res<Assets<PickupsAsset>>,
+    display_query: Query<&Children, With<BoostDisplay>>,
+    mut text_query: Query<&mut Text>,
+) {
+    let pickups_asset = match pickups_assets.get(&data_assets.pickups) {
+        Some(pickups_asset) => pickups_asset,
+        None => return,
+    };
+
+    let mut lines = game
+        .boosts()
+        .iter()
+        .map(|boost| {
+            format!(
+                "{} {}%",
+                pickups_asset.pickups[boost.kind.0].name.to_uppercase(),
+                ((1. - boost.age()) * 100.).ceil()
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if game.snake.shielded {
+        lines.push("SHIELD".to_string());
+    }
+
+    for children in display_query.iter() {
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = lines.join("\n");
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{
+    cli_arg, error_handler,
+    game::{Game, NextInput},
+    input::{ActionState, InputAction},
+    menu::{button_play, MenuState},
+    pause::PauseState,
+    simulation::replay::{Replay, ReplayPlayer},
+    storage, GameState,
+};
+
+const REPLAY_ARG: &str = "--replay";
+
+/// The inputs of the run currently being played, or of the last one once the
+/// game is over.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct Recording(Replay);
+
+/// While this resource exists the simulation is fed from a replay instead of
+/// the keyboard.
+#[derive(Resource, Deref, DerefMut)]
+pub struct Playback(pub ReplayPlayer);
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Recording>()
+            .add_enter_system(
+                GameState::Playing,
+                reset_recording_system.run_unless_resource_exists::<Playback>(),
+            )
+            .add_fixed_timestep_system(
+                "simulation",
+                0,
+                playback_system
+                    .run_in_state(GameState::Playing)
+                    .run_in_state(PauseState::Running)
+                    .run_if_resource_exists::<Playback>()
+                    .before("tick"),
+            )
+            .add_fixed_timestep_system(
+                "simulation",
+                0,
+                record_system
+                    .run_in_state(GameState::Playing)
+                    .run_in_state(PauseState::Running)
+                    .run_unless_resource_exists::<Playback>()
+                    .before("tick"),
+            )
+            .add_enter_system(GameState::GameOver, save_replay_system.chain(error_handler))
+            .add_system(watch_replay_system.run_in_state(GameState::GameOver))
+            .add_system(
+                button_play
+                    .run_in_state(MenuState::Main)
+                    .run_if_resource_exists::<Playback>(),
+            );
+
+        if let Some(path) = cli_arg(REPLAY_ARG) {
+            match load_replay_file(&path) {
+                Ok(replay) => {
+                    app.insert_resource(Playback(ReplayPlayer::new(replay)));
+                }
+                Err(error) => error!("Could not load replay {}: {}", path, error),
+            }
+        }
+    }
+}
+
+fn load_replay_file(path: &str) -> Result<Replay, String> {
+    let text = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+
+    Replay::from_ron(&text)
+}
+
+fn reset_recording_system(mut recording: ResMut<Recording>) {
+    *recording = Recording::default();
+}
+
+fn playback_system(game: Res<Game>, mut playback: ResMut<Playback>, mut input: ResMut<NextInput>) {
+    input.0 = playback.input(game.ticks() + 1);
+}
+
+fn record_system(game: Res<Game>, input: Res<NextInput>, mut recording: ResMut<Recording>) {
+    recording.record(game.ticks() + 1, &input);
+}
+
+fn save_replay_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    mut recording: ResMut<Recording>,
+    playback: Option<Res<Playback>>,
+) -> Result<(), String> {
+    if let Some(playback) = playback {
+        recording.0 = playback.replay.clone();
+        commands.remove_resource::<Playback>();
+        return Ok(());
+    }
+
+    recording.seed = game.seed();
+    recording.difficulty = game.config().difficulty;
+    recording.speed = game.config().speed.clone();
+    recording.curve = game.config().curve.clone();
+
+    storage::save(
+        &format!("replays/{}-{}.ron", game.seed(), game.ticks()),
+        &recording.to_ron()?,
+    )
+}
+
+fn watch_replay_system(
+    mut commands: Commands,
+    action_state: Res<ActionState>,
+    recording: Res<Recording>,
+) {
+    if action_state.just_pressed(InputAction::WatchReplay) {
+        commands.insert_resource(Playback(ReplayPlayer::new(recording.0.clone())));
+        commands.insert_resource(NextState(GameState::Playing));
+    }
+}
@@ -1,14 +1,14 @@
-use std::cmp;
-
 use bevy::prelude::*;
 use iyes_loopless::prelude::*;
 
 use crate::{
     despawn,
-    enemy::MaxEnemies,
+    game::Game,
+    leaderboard::{spawn_leaderboard, Leaderboard, NewHighScore},
     menu::{button_exit, button_interacted, button_play, ExitButton, PlayButton},
-    snake::Snake,
-    GameState, UiAssets,
+    seed::{seed_text_bundle, Seed},
+    simulation::SimulationEvent,
+    DestroyAfter, GameState, UiAssets,
 };
 
 #[derive(Resource, Default, Deref, DerefMut)]
@@ -20,6 +20,9 @@ struct ScoreDisplay;
 #[derive(Component)]
 struct ScoreText;
 
+#[derive(Component)]
+struct ComboText;
+
 #[derive(Bundle)]
 struct ScoreBundle {
     image_bundle: ImageBundle,
@@ -65,11 +68,15 @@ impl Plugin for ScorePlugin {
                 ConditionSet::new()
                     .run_in_state(GameState::Playing)
                     .with_system(update_score)
-                    .with_system(scale_difficulty)
+                    .with_system(combo_system)
                     .into(),
             )
             .add_exit_system(GameState::Playing, despawn::<ScoreDisplay>)
-            .add_enter_system(GameState::GameOver, spawn_game_over)
+            .add_exit_system(GameState::Playing, despawn::<ComboText>)
+            .add_enter_system(
+                GameState::GameOver,
+                spawn_game_over.after("record_high_score"),
+            )
             .add_system_set(
                 ConditionSet::new()
                     .run_in_state(GameState::GameOver)
@@ -115,7 +122,7 @@ fn spawn_score(mut commands: Commands, ui_assets: Res<UiAssets>) {
 }
 
 fn update_score(
-    snake: Res<Snake>,
+    game: Res<Game>,
     score_display: Query<Entity, With<ScoreDisplay>>,
     query: Query<Entity, With<ScoreText>>,
     ui_assets: Res<UiAssets>,
@@ -126,11 +133,7 @@ fn update_score(
         commands.entity(entity).despawn_recursive();
     }
 
-    score.0 = snake.segments.len() as i32 - 3;
-
-    if score.0 < 0 {
-        score.0 = 0;
-    }
+    score.0 = game.score();
 
     let score_display_entity = score_display.single();
 
@@ -146,13 +149,57 @@ fn update_score(
         .push_children(score_entities.as_slice());
 }
 
-fn scale_difficulty(score: Res<Score>, mut max_enemies: ResMut<MaxEnemies>) {
-    let calculated = (2. * (score.0 as f32 / 5.).log2()).floor() as usize;
+/// Calls out squeezing several enemies with one loop.
+fn combo_system(
+    mut commands: Commands,
+    mut events: EventReader<SimulationEvent>,
+    ui_assets: Res<UiAssets>,
+) {
+    for event in events.iter() {
+        let (kills, bonus) = match event {
+            SimulationEvent::Constricted { kills, bonus } if *bonus > 0 => (kills, bonus),
+            _ => continue,
+        };
 
-    max_enemies.0 = cmp::max(1, calculated);
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::FlexStart,
+                        padding: UiRect {
+                            top: Val::Percent(14.),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    ..default()
+                },
+                ComboText,
+                DestroyAfter(Timer::from_seconds(1.5, TimerMode::Once)),
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    format!("SQUEEZE X{} +{}", kills, bonus),
+                    TextStyle {
+                        font: ui_assets.font.clone(),
+                        font_size: 40.,
+                        color: Color::rgb(1., 0.85, 0.2),
+                    },
+                ));
+            });
+    }
 }
 
-fn spawn_game_over(score: Res<Score>, mut commands: Commands, ui_assets: Res<UiAssets>) {
+fn spawn_game_over(
+    score: Res<Score>,
+    game: Res<Game>,
+    leaderboard: Res<Leaderboard>,
+    new_high_score: Res<NewHighScore>,
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+) {
     commands
         .spawn((
             NodeBundle {
@@ -187,6 +234,10 @@ fn spawn_game_over(score: Res<Score>, mut commands: Commands, ui_assets: Res<UiA
                 ..default()
             });
 
+            parent.spawn(seed_text_bundle(&Seed(game.seed().to_string()), &ui_assets));
+
+            spawn_leaderboard(parent, &leaderboard, &new_high_score, &ui_assets);
+
             parent.spawn((
                 ButtonBundle {
                     style: Style {
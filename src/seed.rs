@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use iyes_loopless::prelude::*;
+
+use crate::{cli_arg, menu::MenuState, UiAssets};
+
+const SEED_ARG: &str = "--seed";
+const SEED_ENV: &str = "SNAKE_SEED";
+const MAX_SEED_LENGTH: usize = 20;
+
+/// The seed every run is started from. Left empty, each run picks a fresh
+/// random seed; anything else is parsed with `parse_seed` so the same text
+/// always plays out the same run.
+#[derive(Resource, Default, Clone, Debug, Deref, DerefMut)]
+pub struct Seed(pub String);
+
+impl Seed {
+    pub fn from_env() -> Self {
+        cli_arg(SEED_ARG)
+            .or_else(|| std::env::var(SEED_ENV).ok())
+            .map(Self)
+            .unwrap_or_default()
+    }
+
+    pub fn value(&self) -> Option<u64> {
+        if self.is_empty() {
+            return None;
+        }
+
+        Some(parse_seed(self))
+    }
+}
+
+/// Numbers are used as-is, anything else (e.g. a date for a daily challenge)
+/// is hashed with FNV-1a so it's stable across platforms and builds.
+pub fn parse_seed(text: &str) -> u64 {
+    text.parse().unwrap_or_else(|_| {
+        text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+    })
+}
+
+#[derive(Component)]
+pub struct SeedText;
+
+pub struct SeedPlugin;
+
+impl Plugin for SeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Seed::from_env()).add_system_set(
+            ConditionSet::new()
+                .run_in_state(MenuState::Main)
+                .with_system(seed_input_system)
+                .with_system(seed_text_system)
+                .into(),
+        );
+    }
+}
+
+pub fn seed_text_bundle(seed: &Seed, ui_assets: &UiAssets) -> impl Bundle {
+    (
+        TextBundle::from_section(
+            seed_label(seed),
+            TextStyle {
+                font: ui_assets.font.clone(),
+                font_size: 32.,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            margin: UiRect {
+                top: Val::Px(20.),
+                ..default()
+            },
+            ..default()
+        }),
+        SeedText,
+    )
+}
+
+fn seed_label(seed: &Seed) -> String {
+    match seed.is_empty() {
+        true => "SEED: RANDOM".to_string(),
+        false => format!("SEED: {}", seed.0),
+    }
+}
+
+fn seed_input_system(
+    mut seed: ResMut<Seed>,
+    mut characters: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        seed.pop();
+    }
+
+    for character in characters.iter() {
+        if seed.len() < MAX_SEED_LENGTH
+            && (character.char.is_ascii_alphanumeric() || character.char == '-')
+        {
+            seed.push(character.char);
+        }
+    }
+}
+
+fn seed_text_system(seed: Res<Seed>, mut query: Query<&mut Text, With<SeedText>>) {
+    if !seed.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = seed_label(&seed);
+    }
+}
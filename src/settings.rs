@@ -0,0 +1,391 @@
+use bevy::{prelude::*, window::WindowMode};
+use bevy_framepace::{FramepaceSettings, Limiter};
+use bevy_pixel_camera::PixelProjection;
+use iyes_loopless::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    despawn, error_handler,
+    menu::{button_interacted, spawn_text_button, MenuState},
+    music::{Fader, Mixer},
+    simulation::difficulty::Difficulty,
+    storage, UiAssets, SCALE,
+};
+
+const SETTINGS_KEY: &str = "settings.ron";
+const FRAME_LIMITS: [Option<u32>; 5] = [None, Some(30), Some(60), Some(120), Some(144)];
+const ZOOM_LEVELS: [i32; 5] = [16, 24, 32, 40, 48];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum DisplayMode {
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl From<DisplayMode> for WindowMode {
+    fn from(mode: DisplayMode) -> Self {
+        match mode {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+            DisplayMode::Fullscreen => WindowMode::Fullscreen,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub display_mode: DisplayMode,
+    /// `None` lets the frame pacer match the monitor's refresh rate.
+    pub frame_limit: Option<u32>,
+    pub zoom: i32,
+    pub difficulty: Difficulty,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            display_mode: DisplayMode::Borderless,
+            frame_limit: Some(60),
+            zoom: SCALE,
+            difficulty: Difficulty::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Result<Self, String> {
+        match storage::load(SETTINGS_KEY)? {
+            Some(text) => ron::from_str(&text).map_err(|error| error.to_string()),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let text = ron::to_string(self).map_err(|error| error.to_string())?;
+
+        storage::save(SETTINGS_KEY, &text)
+    }
+
+    pub fn limiter(&self) -> Limiter {
+        match self.frame_limit {
+            Some(framerate) => Limiter::from_framerate(framerate as f64),
+            None => Limiter::Auto,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum MixerFader {
+    Master,
+    Music,
+    Effects,
+}
+
+impl MixerFader {
+    const ALL: [MixerFader; 3] = [MixerFader::Master, MixerFader::Music, MixerFader::Effects];
+
+    fn name(&self) -> &'static str {
+        match self {
+            MixerFader::Master => "MASTER",
+            MixerFader::Music => "MUSIC",
+            MixerFader::Effects => "EFFECTS",
+        }
+    }
+
+    fn get<'a>(&self, mixer: &'a Mixer) -> &'a Fader {
+        match self {
+            MixerFader::Master => &mixer.master,
+            MixerFader::Music => &mixer.music,
+            MixerFader::Effects => &mixer.effects,
+        }
+    }
+
+    fn get_mut<'a>(&self, mixer: &'a mut Mixer) -> &'a mut Fader {
+        match self {
+            MixerFader::Master => &mut mixer.master,
+            MixerFader::Music => &mut mixer.music,
+            MixerFader::Effects => &mut mixer.effects,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Setting {
+    DisplayMode,
+    FrameLimit,
+    Zoom,
+    Difficulty,
+    Volume(MixerFader),
+    Mute(MixerFader),
+}
+
+impl Setting {
+    const DISPLAY: [Setting; 3] = [Setting::DisplayMode, Setting::FrameLimit, Setting::Zoom];
+
+    fn label(&self, settings: &Settings, mixer: &Mixer) -> String {
+        match self {
+            Setting::DisplayMode => format!("WINDOW: {:?}", settings.display_mode).to_uppercase(),
+            Setting::FrameLimit => match settings.frame_limit {
+                Some(framerate) => format!("FRAME LIMIT: {}", framerate),
+                None => "FRAME LIMIT: AUTO".to_string(),
+            },
+            Setting::Zoom => format!("ZOOM: {}", settings.zoom),
+            Setting::Difficulty => format!("DIFFICULTY: {}", settings.difficulty.name()),
+            Setting::Volume(fader) => format!(
+                "{}: {}%",
+                fader.name(),
+                (fader.get(mixer).volume * 100.).round()
+            ),
+            Setting::Mute(fader) => match fader.get(mixer).muted {
+                true => "MUTED".to_string(),
+                false => "MUTE".to_string(),
+            },
+        }
+    }
+
+    fn cycle(&self, settings: &mut Settings, mixer: &mut Mixer) {
+        match self {
+            Setting::DisplayMode => {
+                settings.display_mode = match settings.display_mode {
+                    DisplayMode::Windowed => DisplayMode::Borderless,
+                    DisplayMode::Borderless => DisplayMode::Fullscreen,
+                    DisplayMode::Fullscreen => DisplayMode::Windowed,
+                }
+            }
+            Setting::FrameLimit => settings.frame_limit = next(&FRAME_LIMITS, settings.frame_limit),
+            Setting::Zoom => settings.zoom = next(&ZOOM_LEVELS, settings.zoom),
+            Setting::Difficulty => {
+                settings.difficulty = next(&Difficulty::ALL, settings.difficulty)
+            }
+            Setting::Volume(fader) => {
+                let fader = fader.get_mut(mixer);
+                fader.volume = next_volume(fader.volume);
+            }
+            Setting::Mute(fader) => {
+                let fader = fader.get_mut(mixer);
+                fader.muted = !fader.muted;
+            }
+        }
+    }
+}
+
+fn next<T: Copy + PartialEq>(values: &[T], current: T) -> T {
+    let index = values
+        .iter()
+        .position(|value| *value == current)
+        .map_or(0, |index| index + 1);
+
+    values[index % values.len()]
+}
+
+/// Steps the volume up by 10%, wrapping back to silence after full volume.
+fn next_volume(volume: f64) -> f64 {
+    let step = (volume * 10.).round() as i32 + 1;
+
+    (step % 11) as f64 / 10.
+}
+
+#[derive(Component)]
+struct OnSettings;
+
+#[derive(Component)]
+struct SettingButton(Setting);
+
+#[derive(Component)]
+struct SettingText(Setting);
+
+#[derive(Component)]
+struct BackButton;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(apply_settings_system)
+            .add_enter_system(MenuState::Settings, settings_setup_system)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(MenuState::Settings)
+                    .with_system(setting_button_system)
+                    .with_system(setting_text_system)
+                    .with_system(button_back.run_if(button_interacted::<BackButton>))
+                    .into(),
+            )
+            .add_exit_system(
+                MenuState::Settings,
+                save_settings_system.chain(error_handler),
+            )
+            .add_exit_system(MenuState::Settings, despawn::<OnSettings>);
+    }
+}
+
+fn apply_settings_system(
+    settings: Res<Settings>,
+    mut windows: ResMut<Windows>,
+    mut framepace: ResMut<FramepaceSettings>,
+    mut projections: Query<&mut PixelProjection>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    if let Some(window) = windows.get_primary_mut() {
+        let mode = WindowMode::from(settings.display_mode);
+
+        if window.mode() != mode {
+            window.set_mode(mode);
+        }
+    }
+
+    framepace.limiter = settings.limiter();
+
+    for mut projection in projections.iter_mut() {
+        projection.zoom = settings.zoom;
+    }
+}
+
+fn settings_setup_system(
+    mut commands: Commands,
+    ui_assets: Res<UiAssets>,
+    settings: Res<Settings>,
+    mixer: Res<Mixer>,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            OnSettings,
+        ))
+        .with_children(|parent| {
+            for setting in Setting::DISPLAY {
+                spawn_setting_button(parent, setting, 500., &settings, &mixer, &ui_assets);
+            }
+
+            spawn_setting_button(
+                parent,
+                Setting::Difficulty,
+                500.,
+                &settings,
+                &mixer,
+                &ui_assets,
+            );
+
+            for fader in MixerFader::ALL {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(500.), Val::Auto),
+                            justify_content: JustifyContent::SpaceBetween,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        spawn_setting_button(
+                            parent,
+                            Setting::Volume(fader),
+                            370.,
+                            &settings,
+                            &mixer,
+                            &ui_assets,
+                        );
+                        spawn_setting_button(
+                            parent,
+                            Setting::Mute(fader),
+                            120.,
+                            &settings,
+                            &mixer,
+                            &ui_assets,
+                        );
+                    });
+            }
+
+            spawn_text_button(parent, "BACK", &ui_assets, BackButton);
+        });
+}
+
+fn spawn_setting_button(
+    parent: &mut ChildBuilder,
+    setting: Setting,
+    width: f32,
+    settings: &Settings,
+    mixer: &Mixer,
+    ui_assets: &UiAssets,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    size: Size::new(Val::Px(width), Val::Px(50.)),
+                    margin: UiRect {
+                        top: Val::Px(10.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                ..default()
+            },
+            SettingButton(setting),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    setting.label(settings, mixer),
+                    TextStyle {
+                        font: ui_assets.font.clone(),
+                        font_size: 28.,
+                        color: Color::WHITE,
+                    },
+                ),
+                SettingText(setting),
+            ));
+        });
+}
+
+fn setting_button_system(
+    mut settings: ResMut<Settings>,
+    mut mixer: ResMut<Mixer>,
+    query: Query<(&Interaction, &SettingButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction == Interaction::Clicked {
+            button.0.cycle(&mut settings, &mut mixer);
+        }
+    }
+}
+
+fn setting_text_system(
+    settings: Res<Settings>,
+    mixer: Res<Mixer>,
+    mut query: Query<(&mut Text, &SettingText)>,
+) {
+    if !settings.is_changed() && !mixer.is_changed() {
+        return;
+    }
+
+    for (mut text, setting_text) in query.iter_mut() {
+        text.sections[0].value = setting_text.0.label(&settings, &mixer);
+    }
+}
+
+fn button_back(mut commands: Commands) {
+    commands.insert_resource(NextState(MenuState::Main));
+}
+
+fn save_settings_system(settings: Res<Settings>, mixer: Res<Mixer>) -> Result<(), String> {
+    settings.save()?;
+    mixer.save()
+}
@@ -0,0 +1,604 @@
+//! The game rules, free of any rendering or audio. A `Simulation` advances one
+//! fixed tick at a time from a `TickInput` and reports what happened as
+//! `SimulationEvent`s, which the Bevy plugins turn into sprites and sounds.
+
+use std::{collections::VecDeque, time::Duration};
+
+use glam::Vec2;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use self::{
+    difficulty::{Difficulty, DifficultyCurve},
+    director::{Director, DirectorConfig, PendingSpawn},
+    enemy::{Enemy, EnemyId, EnemyKind, EnemyStats},
+    grid::Grid,
+    level::Level,
+    pickup::{Boost, Pickup, PickupConfig, PickupId, PickupKind},
+    projectile::{Projectile, ProjectileId},
+    snake::{Direction, Snake},
+    speed::SpeedConfig,
+    upgrade::{Progression, UpgradeId, UpgradeStats},
+};
+
+pub mod behaviour;
+pub mod boss;
+pub mod constrict;
+pub mod difficulty;
+pub mod director;
+pub mod enemy;
+pub mod grid;
+pub mod level;
+pub mod pathfinding;
+pub mod pickup;
+pub mod projectile;
+pub mod replay;
+pub mod snake;
+pub mod speed;
+pub mod upgrade;
+pub mod weapon;
+
+pub const SIMULATION_TIMESTEP: u64 = 25;
+const MAX_QUEUED_TURNS: usize = 3;
+const PREDICTION_STEPS: usize = 8;
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Position {
+    pub fn as_tuple(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+}
+
+impl From<Vec2> for Position {
+    fn from(vec: Vec2) -> Self {
+        Self {
+            x: vec.x.floor() as i32,
+            y: vec.y.floor() as i32,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeathCause {
+    Bumped,
+    Damage,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SimulationEvent {
+    SnakeMoved,
+    SnakeAte {
+        id: EnemyId,
+        position: Position,
+    },
+    SnakeHit {
+        position: Position,
+    },
+    SnakeDied {
+        cause: DeathCause,
+    },
+    LevelUp {
+        level: u32,
+    },
+    UpgradeOffered,
+    UpgradePicked {
+        id: UpgradeId,
+    },
+    TailWhipped {
+        position: Position,
+    },
+    Dashed {
+        from: Position,
+        to: Position,
+    },
+    WaveStarted {
+        wave: usize,
+    },
+    SpawnTelegraphed {
+        id: EnemyId,
+        kind: EnemyKind,
+        position: Position,
+    },
+    EnemySpawned {
+        id: EnemyId,
+        kind: EnemyKind,
+    },
+    EnemyWindUp {
+        id: EnemyId,
+        kind: EnemyKind,
+    },
+    EnemyAttacked {
+        id: EnemyId,
+        kind: EnemyKind,
+    },
+    EnemyHit {
+        id: EnemyId,
+        position: Position,
+    },
+    BossPhaseChanged {
+        id: EnemyId,
+        phase: usize,
+    },
+    BossDefeated {
+        id: EnemyId,
+        kind: EnemyKind,
+    },
+    EnemyKilled {
+        id: EnemyId,
+        position: Position,
+    },
+    Constricted {
+        kills: usize,
+        bonus: usize,
+    },
+    ProjectileFired {
+        id: ProjectileId,
+    },
+    CrateBroken {
+        position: Position,
+    },
+    PickupSpawned {
+        id: PickupId,
+        kind: PickupKind,
+    },
+    PickupCollected {
+        id: PickupId,
+        kind: PickupKind,
+        position: Position,
+    },
+    ShieldBroken {
+        position: Position,
+    },
+    BombExploded {
+        position: Position,
+        radius: i32,
+    },
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TickInput {
+    pub turns: Vec<Direction>,
+    /// Which of the offered upgrades to take.
+    pub pick: Option<usize>,
+}
+
+/// Everything a run is set up with besides its seed. Replays store the seed,
+/// the inputs and the difficulty, so they play back exactly as long as the
+/// rest of this stays the same.
+#[derive(Clone, Debug, Default)]
+pub struct SimulationConfig {
+    /// The enemy archetypes that can spawn, indexed by `EnemyKind`.
+    pub enemies: Vec<EnemyStats>,
+    pub level: Level,
+    pub director: DirectorConfig,
+    /// The upgrade registry, indexed by `UpgradeId`.
+    pub upgrades: Vec<UpgradeStats>,
+    pub pickups: PickupConfig,
+    pub speed: SpeedConfig,
+    /// The preset the run is played on, kept for the records.
+    pub difficulty: Difficulty,
+    pub curve: DifficultyCurve,
+}
+
+#[derive(Clone, Debug)]
+pub struct Countdown {
+    duration: Duration,
+    elapsed: Duration,
+    repeating: bool,
+}
+
+impl Countdown {
+    pub fn once(seconds: f32) -> Self {
+        Self {
+            duration: Duration::from_secs_f32(seconds),
+            elapsed: Duration::ZERO,
+            repeating: false,
+        }
+    }
+
+    pub fn repeating(seconds: f32) -> Self {
+        Self {
+            repeating: true,
+            ..Self::once(seconds)
+        }
+    }
+
+    /// Advances the countdown and returns whether it ran out during this tick.
+    pub fn tick(&mut self, delta: Duration) -> bool {
+        if !self.repeating && self.finished() {
+            return false;
+        }
+
+        self.elapsed += delta;
+
+        if self.elapsed < self.duration {
+            return false;
+        }
+
+        if self.repeating && !self.duration.is_zero() {
+            while self.elapsed >= self.duration {
+                self.elapsed -= self.duration;
+            }
+        } else {
+            self.elapsed = self.duration;
+        }
+
+        true
+    }
+
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    pub fn percent(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.;
+        }
+
+        self.elapsed.as_secs_f32() / self.duration.as_secs_f32()
+    }
+}
+
+pub struct Simulation {
+    pub snake: Snake,
+    pub enemies: Vec<Enemy>,
+    pub projectiles: Vec<Projectile>,
+    pub pending_spawns: Vec<PendingSpawn>,
+    pub pickups: Vec<Pickup>,
+    pub grid: Grid,
+    pub enemies_eaten: u32,
+    config: SimulationConfig,
+    director: Director,
+    progression: Progression,
+    boosts: Vec<Boost>,
+    pickup_timer: Countdown,
+    bosses_met: Vec<EnemyKind>,
+    direction: Direction,
+    turns: VecDeque<Direction>,
+    ticks: u64,
+    /// Ticks spent waiting for an upgrade to be picked, which don't count
+    /// as time played.
+    paused_ticks: u64,
+    /// Ticks since the snake last stepped.
+    step_counter: u64,
+    seed: u64,
+    rng: StdRng,
+    next_id: u32,
+    game_over: bool,
+}
+
+impl Simulation {
+    pub fn new(seed: u64, config: SimulationConfig) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let level = &config.level;
+        let direction = level.start_direction;
+        let spawn_point = level
+            .spawn_points
+            .choose(&mut rng)
+            .copied()
+            .unwrap_or(Position { x: 0, y: 0 });
+
+        Self {
+            snake: Snake::new(spawn_point, direction),
+            enemies: Vec::new(),
+            projectiles: Vec::new(),
+            pending_spawns: Vec::new(),
+            pickups: Vec::new(),
+            grid: level.grid.clone(),
+            enemies_eaten: 0,
+            director: Director::new(&config.director, &config.enemies, &mut rng),
+            progression: Progression::new(config.upgrades.len()),
+            boosts: Vec::new(),
+            pickup_timer: Countdown::repeating(config.pickups.interval),
+            config,
+            bosses_met: Vec::new(),
+            direction,
+            turns: VecDeque::new(),
+            ticks: 0,
+            paused_ticks: 0,
+            step_counter: 0,
+            seed,
+            rng,
+            next_id: 0,
+            game_over: false,
+        }
+    }
+
+    pub fn timestep() -> Duration {
+        Duration::from_millis(SIMULATION_TIMESTEP)
+    }
+
+    pub fn config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Time played, leaving out the time spent picking upgrades.
+    pub fn elapsed(&self) -> Duration {
+        Self::timestep() * (self.ticks - self.paused_ticks) as u32
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.game_over
+    }
+
+    pub fn progression(&self) -> &Progression {
+        &self.progression
+    }
+
+    pub fn score(&self) -> i32 {
+        (self.snake.segments.len() as i32 - 3).max(0)
+    }
+
+    pub fn tick(&mut self, input: TickInput) -> Vec<SimulationEvent> {
+        let mut events = Vec::new();
+
+        if self.game_over {
+            return events;
+        }
+
+        self.ticks += 1;
+
+        if let Some(choice) = input.pick {
+            self.pick_upgrade(choice, &mut events);
+        }
+
+        // The run stands still while an upgrade is being picked
+        if self.is_choosing_upgrade() {
+            self.paused_ticks += 1;
+            return events;
+        }
+
+        for turn in input.turns {
+            self.queue_turn(turn);
+        }
+
+        if self.step_due() {
+            self.step_snake(&mut events);
+
+            if self.game_over {
+                return events;
+            }
+
+            self.constrict(&mut events);
+        }
+
+        self.collect_pickups(&mut events);
+
+        // Slowed time lets enemies and their shots move on every other tick
+        let slowed = self.is_time_slowed() && self.ticks % 2 == 1;
+
+        if !slowed {
+            self.move_projectiles(&mut events);
+
+            if self.game_over {
+                return events;
+            }
+        }
+
+        self.spawn_bosses(&mut events);
+        self.direct_spawns(&mut events);
+
+        if !slowed {
+            self.update_enemies(&mut events);
+
+            if self.game_over {
+                return events;
+            }
+        }
+
+        self.use_weapons(&mut events);
+
+        if self.game_over {
+            return events;
+        }
+
+        self.update_pickups(&mut events);
+
+        events
+    }
+
+    fn next_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn kill_snake(&mut self, cause: DeathCause, events: &mut Vec<SimulationEvent>) {
+        if self.game_over {
+            return;
+        }
+
+        self.game_over = true;
+        events.push(SimulationEvent::SnakeDied { cause });
+    }
+
+    fn damage_snake(
+        &mut self,
+        amount: usize,
+        position: Position,
+        events: &mut Vec<SimulationEvent>,
+    ) {
+        if !self.snake.damage(amount) {
+            events.push(SimulationEvent::ShieldBroken { position });
+            return;
+        }
+
+        events.push(SimulationEvent::SnakeHit { position });
+
+        if self.snake.is_dead() {
+            self.kill_snake(DeathCause::Damage, events);
+        }
+    }
+
+    /// Queues a turn for one of the coming snake steps. Turns are checked
+    /// against the direction the snake will have once the turns before them
+    /// are taken, so a quick U-turn through two queued turns is allowed.
+    pub fn queue_turn(&mut self, direction: Direction) -> bool {
+        let current = self.turns.back().copied().unwrap_or(self.direction);
+
+        if self.turns.len() >= MAX_QUEUED_TURNS
+            || direction == current
+            || direction == current.opposite()
+        {
+            return false;
+        }
+
+        self.turns.push_back(direction);
+        true
+    }
+
+    fn step_snake(&mut self, events: &mut Vec<SimulationEvent>) {
+        if let Some(turn) = self.turns.pop_front() {
+            self.direction = turn;
+        }
+
+        self.advance_snake(events);
+    }
+
+    /// Moves the snake one tile the way it is facing.
+    fn advance_snake(&mut self, events: &mut Vec<SimulationEvent>) {
+        self.snake.advance(self.direction);
+        events.push(SimulationEvent::SnakeMoved);
+
+        let head = *self.snake.head();
+
+        if !self.grid.is_open(&head) || self.snake.bit_itself() {
+            self.kill_snake(DeathCause::Bumped, events);
+            return;
+        }
+
+        if let Some(index) = self.enemies.iter().position(|enemy| enemy.position == head) {
+            if self.bite_enemy(index, events) {
+                return;
+            }
+
+            let enemy = self.enemies.remove(index);
+
+            events.push(SimulationEvent::SnakeAte {
+                id: enemy.id,
+                position: head,
+            });
+            self.reward_kill(&enemy, events);
+        }
+    }
+
+    /// Where the head will go over the next few steps if the snake takes its
+    /// queued turns and then keeps going straight, up to the first obstacle.
+    fn predicted_path(&self) -> Vec<Position> {
+        let mut path = Vec::new();
+        let mut head = *self.snake.head();
+        let mut direction = self.direction;
+
+        for step in 0..PREDICTION_STEPS {
+            if let Some(turn) = self.turns.get(step) {
+                direction = *turn;
+            }
+
+            head = direction.step(head);
+
+            if !self.grid.is_open(&head) {
+                break;
+            }
+
+            path.push(head);
+        }
+
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::upgrade::Effect;
+
+    #[test]
+    fn queue_turn_only_allows_u_turns_through_two_turns() {
+        let mut simulation = Simulation::new(0, SimulationConfig::default());
+        let forward = simulation.direction();
+
+        assert!(!simulation.queue_turn(forward));
+        assert!(!simulation.queue_turn(forward.opposite()));
+
+        let side = match forward {
+            Direction::Left | Direction::Right => Direction::Up,
+            Direction::Up | Direction::Down => Direction::Left,
+        };
+
+        assert!(simulation.queue_turn(side));
+        assert!(!simulation.queue_turn(side.opposite()));
+        assert!(simulation.queue_turn(forward.opposite()));
+        assert!(simulation.queue_turn(side));
+        assert!(!simulation.queue_turn(forward));
+    }
+
+    #[test]
+    fn repeating_countdown_keeps_the_overshoot() {
+        let mut countdown = Countdown::repeating(0.125);
+
+        assert!(!countdown.tick(Duration::from_millis(100)));
+        assert!(countdown.tick(Duration::from_millis(50)));
+        assert_eq!(countdown.elapsed, Duration::from_millis(25));
+
+        // Several periods in one tick still leave only the remainder
+        assert!(countdown.tick(Duration::from_millis(230)));
+        assert_eq!(countdown.elapsed, Duration::from_millis(5));
+    }
+
+    #[test]
+    fn countdown_runs_out_once() {
+        let mut countdown = Countdown::once(0.05);
+
+        assert!(!countdown.tick(Duration::from_millis(25)));
+        assert!(countdown.tick(Duration::from_millis(50)));
+        assert!(countdown.finished());
+        assert_eq!(countdown.percent(), 1.);
+        assert!(!countdown.tick(Duration::from_millis(25)));
+    }
+
+    #[test]
+    fn picking_upgrades_does_not_count_as_time_played() {
+        let mut simulation = Simulation::new(
+            0,
+            SimulationConfig {
+                upgrades: vec![UpgradeStats {
+                    max_stacks: 1,
+                    effect: Effect::Growth(1),
+                }],
+                ..Default::default()
+            },
+        );
+        let xp = simulation.progression.xp_to_next();
+
+        simulation.gain_xp(xp, &mut Vec::new());
+
+        for _ in 0..100 {
+            simulation.tick(TickInput::default());
+        }
+
+        assert_eq!(simulation.ticks(), 100);
+        assert_eq!(simulation.elapsed(), Duration::ZERO);
+
+        simulation.tick(TickInput {
+            pick: Some(0),
+            ..Default::default()
+        });
+
+        assert_eq!(simulation.elapsed(), Simulation::timestep());
+    }
+}
@@ -0,0 +1,200 @@
+//! How idle enemies choose what to do next. Each archetype names a brain in
+//! its stats; the brain looks at the snake and returns an `Action`, which the
+//! `EnemyState` machine in `enemy` then carries out.
+
+use rand::{seq::SliceRandom, Rng, RngCore};
+use serde::Deserialize;
+
+use super::{enemy::MoveGoal, pathfinding::distance, snake::Direction, Position};
+
+/// How far the scores of a utility brain are randomly nudged, so enemies
+/// with close options don't all make the same choice on the same tick.
+const SCORE_JITTER: f32 = 0.15;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Wait,
+    Attack,
+    Move(MoveGoal),
+}
+
+/// What a deciding enemy knows about the snake.
+pub struct Situation<'a> {
+    pub position: Position,
+    pub head: Position,
+    pub direction: Direction,
+    pub length: usize,
+    /// Where the head will be over the coming steps if the snake takes its
+    /// queued turns and then keeps going straight.
+    pub predicted_path: &'a [Position],
+    pub segments: &'a [Position],
+}
+
+impl Situation<'_> {
+    pub fn distance_to_head(&self) -> i32 {
+        distance(&self.position, &self.head)
+    }
+
+    /// Whether the snake's next step takes its head closer to the enemy.
+    pub fn snake_approaching(&self) -> bool {
+        distance(&self.position, &self.direction.step(self.head)) < self.distance_to_head()
+    }
+
+    /// Whether a tile next to the enemy, where melee attacks land, is on the
+    /// snake or on the first `lead` tiles of its predicted path.
+    pub fn path_adjacent(&self, lead: usize) -> bool {
+        Direction::ALL.iter().any(|direction| {
+            let tile = direction.step(self.position);
+
+            self.segments.contains(&tile)
+                || self
+                    .predicted_path
+                    .iter()
+                    .take(lead)
+                    .any(|position| *position == tile)
+        })
+    }
+
+    /// Which way a melee attack should go: at a segment right next to the
+    /// enemy if there is one, then at where the head is about to pass, and
+    /// otherwise roughly towards the head.
+    pub fn strike_direction(&self) -> Direction {
+        let adjacent = |tile: &Position| {
+            Direction::ALL
+                .into_iter()
+                .find(|direction| direction.step(self.position) == *tile)
+        };
+
+        if let Some(direction) = self.segments.iter().find_map(adjacent) {
+            return direction;
+        }
+
+        if let Some(direction) = self.predicted_path.iter().find_map(adjacent) {
+            return direction;
+        }
+
+        Direction::towards(&self.position, &self.head)
+    }
+}
+
+pub trait Brain {
+    fn decide(&self, situation: &Situation, rng: &mut dyn RngCore) -> Action;
+}
+
+/// Picks the highest scoring action after nudging every score a little.
+fn best(options: &[(Action, f32)], rng: &mut dyn RngCore) -> Action {
+    options
+        .iter()
+        .map(|(action, score)| (*action, score + rng.gen_range(0.0..SCORE_JITTER)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map_or(Action::Wait, |(action, _)| action)
+}
+
+/// Ignores the snake and rolls for a state with fixed weights.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct RandomBrain {
+    pub idle: u32,
+    pub attack: u32,
+    pub moving: u32,
+    #[serde(default)]
+    pub goal: MoveGoal,
+}
+
+impl Brain for RandomBrain {
+    fn decide(&self, _situation: &Situation, rng: &mut dyn RngCore) -> Action {
+        let actions = [Action::Wait, Action::Attack, Action::Move(self.goal)];
+
+        actions
+            .choose_weighted(rng, |action| match action {
+                Action::Wait => self.idle,
+                Action::Attack => self.attack,
+                Action::Move(_) => self.moving,
+            })
+            .copied()
+            .unwrap_or(Action::Wait)
+    }
+}
+
+/// Gets next to where the snake is heading and strikes as it passes.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct AmbusherBrain {
+    /// How many steps ahead of the head to lie in wait.
+    pub lead: usize,
+}
+
+impl Brain for AmbusherBrain {
+    fn decide(&self, situation: &Situation, rng: &mut dyn RngCore) -> Action {
+        let in_position = situation.path_adjacent(self.lead);
+        // A longer snake is easier to hit, so it's worth chasing harder
+        let eagerness = situation.length.min(20) as f32 * 0.01;
+
+        best(
+            &[
+                (Action::Attack, if in_position { 1. } else { 0.1 }),
+                (
+                    Action::Move(MoveGoal::Intercept { lead: self.lead }),
+                    if in_position { 0.1 } else { 0.6 + eagerness },
+                ),
+                (Action::Wait, if in_position { 0.6 } else { 0.3 }),
+            ],
+            rng,
+        )
+    }
+}
+
+/// Keeps the snake between `min_range` and `max_range` tiles away and fires
+/// from there.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct SkirmisherBrain {
+    pub min_range: i32,
+    pub max_range: i32,
+}
+
+impl Brain for SkirmisherBrain {
+    fn decide(&self, situation: &Situation, rng: &mut dyn RngCore) -> Action {
+        let distance = situation.distance_to_head();
+        let too_close = distance < self.min_range;
+        let too_far = distance > self.max_range;
+
+        let flee = match (too_close, situation.snake_approaching()) {
+            (true, true) => 1.,
+            (true, false) => 0.8,
+            (false, _) => 0.05,
+        };
+
+        let attack = match too_close || too_far {
+            true => 0.2,
+            false => 0.7 + situation.length.min(20) as f32 * 0.005,
+        };
+
+        best(
+            &[
+                (Action::Move(MoveGoal::Flee), flee),
+                (Action::Attack, attack),
+                (
+                    Action::Move(MoveGoal::Flank),
+                    if too_far { 0.6 } else { 0.05 },
+                ),
+                (Action::Wait, 0.3),
+            ],
+            rng,
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum Behaviour {
+    Random(RandomBrain),
+    Ambusher(AmbusherBrain),
+    Skirmisher(SkirmisherBrain),
+}
+
+impl Behaviour {
+    pub fn brain(&self) -> &dyn Brain {
+        match self {
+            Behaviour::Random(brain) => brain,
+            Behaviour::Ambusher(brain) => brain,
+            Behaviour::Skirmisher(brain) => brain,
+        }
+    }
+}
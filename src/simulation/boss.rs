@@ -0,0 +1,119 @@
+//! Bosses are enemy archetypes with a `boss` section in their stats. Each one
+//! shows up once, when the score first reaches its threshold, and switches
+//! to harsher attacks as it loses health.
+
+use serde::Deserialize;
+
+use super::{
+    behaviour::Behaviour,
+    enemy::{AttackKind, Enemy, EnemyKind},
+    Simulation, SimulationEvent,
+};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BossStats {
+    /// The score at which the boss arrives.
+    pub score: i32,
+    /// Extra segments the snake grows when it eats the boss.
+    pub reward: usize,
+    /// Phases that take over from the boss's base stats as it gets hurt.
+    #[serde(default)]
+    pub phases: Vec<BossPhase>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BossPhase {
+    /// The phase starts once the boss is down to this fraction of its health.
+    pub below: f32,
+    pub behaviour: Behaviour,
+    pub attack: AttackKind,
+    pub wind_up: f32,
+}
+
+impl BossStats {
+    /// Which phase a boss with `health` left is in. Phase 0 is the base stats.
+    pub fn phase_for(&self, health: usize, max_health: usize) -> usize {
+        let fraction = health as f32 / max_health.max(1) as f32;
+
+        self.phases
+            .iter()
+            .filter(|phase| fraction <= phase.below)
+            .count()
+    }
+
+    pub fn phase(&self, phase: usize) -> Option<&BossPhase> {
+        self.phases.get(phase.checked_sub(1)?)
+    }
+}
+
+impl Simulation {
+    pub fn is_boss(&self, enemy: &Enemy) -> bool {
+        self.config.enemies[enemy.kind.0].boss.is_some()
+    }
+
+    pub(super) fn boss_active(&self) -> bool {
+        self.enemies.iter().any(|enemy| self.is_boss(enemy))
+            || self
+                .pending_spawns
+                .iter()
+                .any(|spawn| self.config.enemies[spawn.kind.0].boss.is_some())
+    }
+
+    pub(super) fn spawn_bosses(&mut self, events: &mut Vec<SimulationEvent>) {
+        let score = self.score();
+        let due = self
+            .config
+            .enemies
+            .iter()
+            .enumerate()
+            .filter_map(|(index, stats)| match &stats.boss {
+                Some(boss) if boss.score <= score => Some(EnemyKind(index)),
+                _ => None,
+            })
+            .find(|kind| !self.bosses_met.contains(kind));
+
+        let kind = match due {
+            Some(kind) => kind,
+            None => return,
+        };
+
+        if self.telegraph_spawn(kind, events) {
+            self.bosses_met.push(kind);
+        }
+    }
+
+    /// Moves a hurt boss on to the phase that matches its health.
+    pub(super) fn update_boss_phase(&mut self, index: usize, events: &mut Vec<SimulationEvent>) {
+        let enemy = &mut self.enemies[index];
+        let stats = &self.config.enemies[enemy.kind.0];
+        let phase = match &stats.boss {
+            Some(boss) => boss.phase_for(enemy.health, stats.health),
+            None => return,
+        };
+
+        if phase != enemy.phase {
+            enemy.phase = phase;
+            enemy.reset_attack_animation_timer(stats);
+            events.push(SimulationEvent::BossPhaseChanged {
+                id: enemy.id,
+                phase,
+            });
+        }
+    }
+
+    pub(super) fn defeat_boss(&mut self, enemy: &Enemy, events: &mut Vec<SimulationEvent>) {
+        let reward = match &self.config.enemies[enemy.kind.0].boss {
+            Some(boss) => boss.reward,
+            None => return,
+        };
+
+        for _ in 0..reward {
+            self.snake.grow();
+        }
+
+        events.push(SimulationEvent::BossDefeated {
+            id: enemy.id,
+            kind: enemy.kind,
+        });
+    }
+}
@@ -0,0 +1,212 @@
+//! The snake's body as a weapon. Whenever the snake closes a loop, every
+//! enemy caught inside it gets squeezed; several at once earn extra growth.
+
+use std::collections::VecDeque;
+
+use super::{snake::Direction, Position, Simulation, SimulationEvent};
+
+/// Damage dealt on every step an enemy spends inside a loop. Armor doesn't
+/// help against it.
+const CONSTRICT_DAMAGE: usize = 3;
+
+impl Simulation {
+    /// Which of `targets` the snake's body cuts off from the edge of the
+    /// grid. Walls don't count as part of a loop, only segments do.
+    fn enclosed(&self, targets: &[Position]) -> Vec<bool> {
+        let tiles = (self.grid.size.x * self.grid.size.y) as usize;
+        let mut body = vec![false; tiles];
+
+        for segment in self.snake.segments.iter() {
+            if let Some(index) = self.grid.index(segment) {
+                body[index] = true;
+            }
+        }
+
+        let (min, max) = (self.grid.min(), self.grid.max());
+        let mut outside = vec![false; tiles];
+        let mut queue = VecDeque::new();
+
+        for position in self.grid.positions().filter(|position| {
+            position.x == min.x || position.x == max.x || position.y == min.y || position.y == max.y
+        }) {
+            let index = self.grid.index(&position).unwrap();
+
+            if !body[index] {
+                outside[index] = true;
+                queue.push_back(position);
+            }
+        }
+
+        while let Some(position) = queue.pop_front() {
+            for direction in Direction::ALL {
+                let next = direction.step(position);
+
+                if let Some(index) = self.grid.index(&next) {
+                    if !body[index] && !outside[index] {
+                        outside[index] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        targets
+            .iter()
+            .map(|target| {
+                self.grid
+                    .index(target)
+                    .map_or(false, |index| !outside[index] && !body[index])
+            })
+            .collect()
+    }
+
+    pub(super) fn constrict(&mut self, events: &mut Vec<SimulationEvent>) {
+        if self.game_over || self.enemies.is_empty() {
+            return;
+        }
+
+        let positions = self
+            .enemies
+            .iter()
+            .map(|enemy| enemy.position)
+            .collect::<Vec<_>>();
+        let enclosed = self.enclosed(&positions);
+
+        if !enclosed.contains(&true) {
+            return;
+        }
+
+        let mut kills = 0;
+        let mut index = 0;
+
+        for inside in enclosed {
+            if !inside {
+                index += 1;
+                continue;
+            }
+
+            if !self.hurt_enemy(index, CONSTRICT_DAMAGE, events) {
+                kills += 1;
+                continue;
+            }
+
+            index += 1;
+        }
+
+        if kills == 0 {
+            return;
+        }
+
+        // Every enemy past the first in one squeeze is worth one more segment
+        // than the one before it
+        let bonus = kills * (kills - 1) / 2;
+
+        for _ in 0..bonus {
+            self.snake.grow();
+        }
+
+        events.push(SimulationEvent::Constricted { kills, bonus });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{
+        enemy::{Enemy, EnemyId, EnemyKind},
+        SimulationConfig,
+    };
+
+    const ENEMIES: &str = r#"#![enable(unwrap_variant_newtypes)]
+[
+    (
+        spawn_weight: 1,
+        decision_time: (1.0, 1.0),
+        behaviour: Ambusher(lead: 4),
+        attack: Melee,
+        wind_up: 0.5,
+        damage: 1,
+        move_step: 0.25,
+    ),
+]"#;
+
+    fn position(x: i32, y: i32) -> Position {
+        Position { x, y }
+    }
+
+    /// A run with the snake wrapped around the two tiles at (5, 5) and (6, 5),
+    /// leaving out the segments in `gaps`.
+    fn simulation(gaps: &[Position]) -> Simulation {
+        let mut simulation = Simulation::new(
+            0,
+            SimulationConfig {
+                enemies: ron::from_str(ENEMIES).unwrap(),
+                ..Default::default()
+            },
+        );
+
+        simulation.snake.segments = (4..=7)
+            .flat_map(|x| (4..=6).map(move |y| position(x, y)))
+            .filter(|segment| segment.y != 5 || segment.x == 4 || segment.x == 7)
+            .filter(|segment| !gaps.contains(segment))
+            .collect::<VecDeque<_>>();
+        simulation
+    }
+
+    fn spawn_enemy(simulation: &mut Simulation, position: Position) {
+        let stats = simulation.config.enemies[0].clone();
+        let id = EnemyId(simulation.enemies.len() as u32);
+        let enemy = Enemy::new(id, EnemyKind(0), &stats, position, &mut simulation.rng);
+
+        simulation.enemies.push(enemy);
+    }
+
+    #[test]
+    fn loops_enclose_the_tiles_inside_them() {
+        let simulation = simulation(&[]);
+
+        assert_eq!(
+            simulation.enclosed(&[
+                position(5, 5),
+                position(6, 5),
+                position(4, 5),
+                position(8, 5),
+                position(-100, 0),
+            ]),
+            vec![true, true, false, false, false]
+        );
+    }
+
+    #[test]
+    fn gaps_in_the_loop_let_the_inside_out() {
+        let targets = [position(5, 5), position(6, 5)];
+
+        assert_eq!(
+            simulation(&[position(7, 5)]).enclosed(&targets),
+            vec![false, false]
+        );
+
+        // The way out is only searched along rows and columns, so a missing
+        // corner still leaves the loop closed
+        assert_eq!(
+            simulation(&[position(4, 4)]).enclosed(&targets),
+            vec![true, true]
+        );
+    }
+
+    #[test]
+    fn squeezing_several_enemies_earns_a_bonus() {
+        let mut simulation = simulation(&[]);
+        let mut events = Vec::new();
+
+        spawn_enemy(&mut simulation, position(5, 5));
+        spawn_enemy(&mut simulation, position(0, -5));
+        spawn_enemy(&mut simulation, position(6, 5));
+
+        simulation.constrict(&mut events);
+
+        assert_eq!(simulation.enemies.len(), 1);
+        assert_eq!(simulation.enemies[0].position, position(0, -5));
+        assert!(events.contains(&SimulationEvent::Constricted { kills: 2, bonus: 1 }));
+    }
+}
@@ -0,0 +1,188 @@
+//! How hard a run gets. A difficulty curve lists points over the score or
+//! the time played; in between two points every value is blended, past the
+//! last one it stays put. Each value scales what the enemy roster, the waves
+//! and the snake's speed settings would otherwise do.
+
+use serde::{Deserialize, Serialize};
+
+use super::Simulation;
+
+/// The preset a run was played on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    /// Read from a curve file the player can edit.
+    Custom,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Custom,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "EASY",
+            Difficulty::Normal => "NORMAL",
+            Difficulty::Hard => "HARD",
+            Difficulty::Custom => "CUSTOM",
+        }
+    }
+}
+
+/// What a curve is laid out over.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CurveAxis {
+    #[default]
+    Score,
+    Seconds,
+}
+
+/// One point on a difficulty curve. Everything but `at` is a multiplier, so
+/// 1 leaves things as they are.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DifficultyPoint {
+    /// The score or second the point sits at.
+    pub at: f32,
+    /// How many enemies can be out at once.
+    pub enemies: f32,
+    /// How long enemies think before they act.
+    pub decision_time: f32,
+    /// How fast enemy projectiles fly.
+    pub projectile_speed: f32,
+    /// How fast the snake moves.
+    pub snake_speed: f32,
+    /// How much damage enemies deal.
+    pub damage: f32,
+}
+
+impl Default for DifficultyPoint {
+    fn default() -> Self {
+        Self {
+            at: 0.,
+            enemies: 1.,
+            decision_time: 1.,
+            projectile_speed: 1.,
+            snake_speed: 1.,
+            damage: 1.,
+        }
+    }
+}
+
+impl DifficultyPoint {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+
+        Self {
+            at: mix(self.at, other.at),
+            enemies: mix(self.enemies, other.enemies),
+            decision_time: mix(self.decision_time, other.decision_time),
+            projectile_speed: mix(self.projectile_speed, other.projectile_speed),
+            snake_speed: mix(self.snake_speed, other.snake_speed),
+            damage: mix(self.damage, other.damage),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DifficultyCurve {
+    #[serde(default)]
+    pub axis: CurveAxis,
+    /// Sorted by `at`. Without any points nothing is scaled.
+    pub points: Vec<DifficultyPoint>,
+}
+
+impl DifficultyCurve {
+    pub fn sample(&self, x: f32) -> DifficultyPoint {
+        let after = self.points.iter().position(|point| point.at > x);
+
+        match after {
+            None => self.points.last().copied().unwrap_or_default(),
+            Some(0) => self.points[0],
+            Some(index) => {
+                let (from, to) = (&self.points[index - 1], &self.points[index]);
+
+                from.lerp(to, (x - from.at) / (to.at - from.at))
+            }
+        }
+    }
+}
+
+impl Simulation {
+    /// Where the run currently is on its difficulty curve.
+    pub fn difficulty(&self) -> DifficultyPoint {
+        let x = match self.config.curve.axis {
+            CurveAxis::Score => self.score() as f32,
+            CurveAxis::Seconds => self.elapsed().as_secs_f32(),
+        };
+
+        self.config.curve.sample(x)
+    }
+
+    /// The director's enemy cap, scaled by the curve. There is always room
+    /// for at least one.
+    pub(super) fn max_alive(&self) -> usize {
+        let enemies = self.config.director.max_alive as f32 * self.difficulty().enemies;
+
+        (enemies.round() as usize).max(1)
+    }
+
+    /// Damage an enemy attack deals after scaling, at least 1.
+    pub(super) fn enemy_damage(&self, damage: usize) -> usize {
+        ((damage as f32 * self.difficulty().damage).round() as usize).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(at: f32, enemies: f32) -> DifficultyPoint {
+        DifficultyPoint {
+            at,
+            enemies,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn empty_curves_scale_nothing() {
+        assert_eq!(
+            DifficultyCurve::default().sample(100.),
+            DifficultyPoint::default()
+        );
+    }
+
+    #[test]
+    fn curves_blend_between_points_and_hold_at_the_ends() {
+        let curve = DifficultyCurve {
+            axis: CurveAxis::Score,
+            points: vec![point(10., 1.), point(20., 2.), point(40., 4.)],
+        };
+
+        assert_eq!(curve.sample(0.), point(10., 1.));
+        assert_eq!(curve.sample(10.), point(10., 1.));
+        assert_eq!(curve.sample(15.), point(15., 1.5));
+        assert_eq!(curve.sample(30.), point(30., 3.));
+        assert_eq!(curve.sample(40.), point(40., 4.));
+        assert_eq!(curve.sample(1000.), point(40., 4.));
+    }
+
+    #[test]
+    fn points_in_the_same_place_jump_straight_to_the_last() {
+        let curve = DifficultyCurve {
+            axis: CurveAxis::Seconds,
+            points: vec![point(0., 1.), point(10., 1.), point(10., 3.)],
+        };
+
+        assert_eq!(curve.sample(5.), point(5., 1.));
+        assert_eq!(curve.sample(10.), point(10., 3.));
+    }
+}
@@ -0,0 +1,457 @@
+//! Decides when and where enemies show up. Enemies come in waves, every
+//! spawn is marked on its tile for a moment before the enemy appears, and
+//! each wave starts with a breather so the player gets some room.
+
+use rand::{seq::SliceRandom, Rng};
+
+use super::{
+    enemy::{Enemy, EnemyId, EnemyKind, EnemyStats},
+    pathfinding::distance,
+    Countdown, Position, Simulation, SimulationEvent,
+};
+
+/// Waves past the configured ones are made up on the spot, with this much
+/// calm, time between spawns and time to clear.
+const ENDLESS_BREATHER: f32 = 3.;
+const ENDLESS_INTERVAL: f32 = 0.75;
+const ENDLESS_CLEAR_TIME: f32 = 20.;
+
+#[derive(Clone, Debug)]
+pub struct Wave {
+    /// Seconds of calm before the wave starts.
+    pub breather: f32,
+    /// Seconds between two spawns of the wave.
+    pub interval: f32,
+    /// Seconds after the last spawn before the next wave comes, even if
+    /// some of this one are still around.
+    pub clear_time: f32,
+    pub enemies: Vec<EnemyKind>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DirectorConfig {
+    /// Seconds a spawn is marked on its tile before the enemy appears.
+    pub telegraph: f32,
+    /// How close to the snake's head enemies may appear. Spawns go on the
+    /// free tile furthest from the head when none is this far away.
+    pub min_distance: i32,
+    /// How many enemies can be out at once, before the difficulty curve
+    /// scales it. A wave waits for room.
+    pub max_alive: usize,
+    /// Once these run out, every wave brings one more enemy than the last,
+    /// picked by spawn weight.
+    pub waves: Vec<Wave>,
+}
+
+impl Default for DirectorConfig {
+    fn default() -> Self {
+        Self {
+            telegraph: 1.,
+            min_distance: 6,
+            max_alive: 6,
+            waves: Vec::new(),
+        }
+    }
+}
+
+/// An enemy that is about to appear.
+#[derive(Clone, Debug)]
+pub struct PendingSpawn {
+    pub id: EnemyId,
+    pub kind: EnemyKind,
+    pub position: Position,
+    /// How far the head has to stay for the enemy to hatch: `min_distance`,
+    /// or less for a spawn that had to go closer than that.
+    clearance: i32,
+    timer: Countdown,
+}
+
+impl PendingSpawn {
+    /// How far along the telegraph is, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        self.timer.percent()
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Stage {
+    Breather(Countdown),
+    Spawning {
+        queue: Vec<EnemyKind>,
+        timer: Countdown,
+    },
+    Clearing(Countdown),
+}
+
+#[derive(Clone, Debug)]
+pub struct Director {
+    /// The number of the current wave, 0 until the first one starts.
+    wave: usize,
+    plan: Wave,
+    stage: Stage,
+}
+
+impl Director {
+    pub(super) fn new(config: &DirectorConfig, enemies: &[EnemyStats], rng: &mut impl Rng) -> Self {
+        let plan = plan_wave(config, enemies, 1, rng);
+
+        Self {
+            wave: 0,
+            stage: Stage::Breather(Countdown::once(plan.breather)),
+            plan,
+        }
+    }
+}
+
+/// The configured wave with the given number, or a made up one past them.
+fn plan_wave(
+    config: &DirectorConfig,
+    enemies: &[EnemyStats],
+    number: usize,
+    rng: &mut impl Rng,
+) -> Wave {
+    if let Some(wave) = config.waves.get(number - 1) {
+        return wave.clone();
+    }
+
+    let size =
+        config.waves.last().map_or(0, |wave| wave.enemies.len()) + number - config.waves.len();
+    let kinds = (0..enemies.len())
+        .filter(|index| enemies[*index].boss.is_none())
+        .collect::<Vec<_>>();
+
+    Wave {
+        breather: ENDLESS_BREATHER,
+        interval: ENDLESS_INTERVAL,
+        clear_time: ENDLESS_CLEAR_TIME,
+        enemies: (0..size)
+            .filter_map(|_| {
+                kinds
+                    .choose_weighted(rng, |index| enemies[*index].spawn_weight)
+                    .ok()
+                    .map(|index| EnemyKind(*index))
+            })
+            .collect(),
+    }
+}
+
+impl Simulation {
+    /// The wave the director is on, 0 before the first one starts.
+    pub fn wave(&self) -> usize {
+        self.director.wave
+    }
+
+    pub(super) fn direct_spawns(&mut self, events: &mut Vec<SimulationEvent>) {
+        self.hatch_spawns(events);
+
+        // Bosses get the arena to themselves
+        if self.boss_active() {
+            return;
+        }
+
+        let delta = Self::timestep();
+        let alive = self.enemies.len() + self.pending_spawns.len();
+        let max_alive = self.max_alive();
+
+        match &mut self.director.stage {
+            Stage::Breather(timer) => {
+                if !timer.tick(delta) {
+                    return;
+                }
+
+                let mut queue = self.director.plan.enemies.clone();
+                queue.shuffle(&mut self.rng);
+
+                self.director.wave += 1;
+                self.director.stage = Stage::Spawning {
+                    queue,
+                    timer: Countdown::once(0.),
+                };
+                events.push(SimulationEvent::WaveStarted {
+                    wave: self.director.wave,
+                });
+            }
+            Stage::Spawning { queue, timer } => {
+                if alive >= max_alive {
+                    return;
+                }
+
+                timer.tick(delta);
+
+                if !timer.finished() {
+                    return;
+                }
+
+                let kind = match queue.last() {
+                    Some(kind) => *kind,
+                    None => {
+                        let clear_time = self.director.plan.clear_time;
+                        self.director.stage = Stage::Clearing(Countdown::once(clear_time));
+                        return;
+                    }
+                };
+
+                *timer = Countdown::once(self.director.plan.interval);
+
+                // With nowhere to put it the enemy stays queued for the next try
+                if self.telegraph_spawn(kind, events) {
+                    if let Stage::Spawning { queue, .. } = &mut self.director.stage {
+                        queue.pop();
+                    }
+                }
+            }
+            Stage::Clearing(timer) => {
+                if !timer.tick(delta) && alive > 0 {
+                    return;
+                }
+
+                let plan = plan_wave(
+                    &self.config.director,
+                    &self.config.enemies,
+                    self.director.wave + 1,
+                    &mut self.rng,
+                );
+
+                self.director.stage = Stage::Breather(Countdown::once(plan.breather));
+                self.director.plan = plan;
+            }
+        }
+    }
+
+    /// Marks a tile away from the snake for an enemy to appear on once the
+    /// telegraph runs out. Returns false if there is nowhere to put it.
+    pub(super) fn telegraph_spawn(
+        &mut self,
+        kind: EnemyKind,
+        events: &mut Vec<SimulationEvent>,
+    ) -> bool {
+        let position = match self.spawn_position() {
+            Some(position) => position,
+            None => return false,
+        };
+
+        let id = EnemyId(self.next_id());
+        let clearance =
+            distance(&position, self.snake.head()).min(self.config.director.min_distance);
+
+        self.pending_spawns.push(PendingSpawn {
+            id,
+            kind,
+            position,
+            clearance,
+            timer: Countdown::once(self.config.director.telegraph),
+        });
+        events.push(SimulationEvent::SpawnTelegraphed { id, kind, position });
+        true
+    }
+
+    /// A free spawn tile at least `min_distance` from the head, or the one
+    /// furthest from it if none are that far.
+    fn spawn_position(&mut self) -> Option<Position> {
+        let head = *self.snake.head();
+        let spawns = match self.config.level.enemy_spawns.is_empty() {
+            true => self.grid.open_positions(),
+            false => self.config.level.enemy_spawns.clone(),
+        };
+        let free = spawns
+            .into_iter()
+            .filter(|position| {
+                !self.snake.contains(position)
+                    && !self.enemies.iter().any(|enemy| enemy.position == *position)
+                    && !self
+                        .pending_spawns
+                        .iter()
+                        .any(|spawn| spawn.position == *position)
+            })
+            .collect::<Vec<_>>();
+        let far = free
+            .iter()
+            .copied()
+            .filter(|position| distance(position, &head) >= self.config.director.min_distance)
+            .collect::<Vec<_>>();
+
+        match far.choose(&mut self.rng) {
+            Some(position) => Some(*position),
+            None => free
+                .into_iter()
+                .max_by_key(|position| distance(position, &head)),
+        }
+    }
+
+    /// Turns telegraphs that have run out into enemies. One whose tile is
+    /// taken, or that the head has come too close to, waits until that
+    /// changes.
+    fn hatch_spawns(&mut self, events: &mut Vec<SimulationEvent>) {
+        let delta = Self::timestep();
+        let head = *self.snake.head();
+        let mut index = 0;
+
+        while index < self.pending_spawns.len() {
+            let spawn = &mut self.pending_spawns[index];
+            spawn.timer.tick(delta);
+
+            let blocked = self.snake.contains(&spawn.position)
+                || distance(&spawn.position, &head) < spawn.clearance
+                || self
+                    .enemies
+                    .iter()
+                    .any(|enemy| enemy.position == spawn.position);
+
+            if !spawn.timer.finished() || blocked {
+                index += 1;
+                continue;
+            }
+
+            let PendingSpawn {
+                id, kind, position, ..
+            } = self.pending_spawns.remove(index);
+            let stats = &self.config.enemies[kind.0];
+
+            self.enemies
+                .push(Enemy::new(id, kind, stats, position, &mut self.rng));
+            events.push(SimulationEvent::EnemySpawned { id, kind });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::SimulationConfig;
+
+    const ENEMIES: &str = r#"#![enable(unwrap_variant_newtypes)]
+[
+    (
+        spawn_weight: 1,
+        decision_time: (1.0, 1.0),
+        behaviour: Ambusher(lead: 4),
+        attack: Melee,
+        wind_up: 0.5,
+        damage: 1,
+        move_step: 0.25,
+    ),
+    (
+        spawn_weight: 0,
+        decision_time: (1.0, 1.0),
+        behaviour: Ambusher(lead: 4),
+        attack: Melee,
+        wind_up: 0.5,
+        damage: 1,
+        move_step: 0.25,
+        boss: Some((score: 1000, reward: 3)),
+    ),
+]"#;
+
+    fn config() -> SimulationConfig {
+        SimulationConfig {
+            enemies: ron::from_str(ENEMIES).unwrap(),
+            director: DirectorConfig {
+                waves: vec![Wave {
+                    breather: 0.5,
+                    interval: 0.25,
+                    clear_time: 5.,
+                    enemies: vec![EnemyKind(0); 3],
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn direct(simulation: &mut Simulation, ticks: usize) -> Vec<SimulationEvent> {
+        let mut events = Vec::new();
+
+        for _ in 0..ticks {
+            simulation.direct_spawns(&mut events);
+        }
+
+        events
+    }
+
+    #[test]
+    fn waves_telegraph_their_enemies_away_from_the_head() {
+        let mut simulation = Simulation::new(0, config());
+        let head = *simulation.snake.head();
+
+        // Half a second of breather, then one spawn every quarter second,
+        // each hatching a second after it was marked
+        let events = direct(&mut simulation, 21);
+
+        assert_eq!(simulation.wave(), 1);
+        assert!(events.contains(&SimulationEvent::WaveStarted { wave: 1 }));
+        assert_eq!(simulation.pending_spawns.len(), 1);
+
+        let events = direct(&mut simulation, 60);
+        let spawned = events
+            .iter()
+            .filter(|event| matches!(event, SimulationEvent::EnemySpawned { .. }))
+            .count();
+
+        assert_eq!(spawned, 3);
+        assert!(simulation
+            .enemies
+            .iter()
+            .all(|enemy| distance(&enemy.position, &head) >= 6));
+    }
+
+    #[test]
+    fn endless_waves_grow_without_bosses() {
+        let config = config();
+        let mut rng = rand::thread_rng();
+
+        for number in 2..6 {
+            let wave = plan_wave(&config.director, &config.enemies, number, &mut rng);
+
+            assert_eq!(wave.enemies.len(), 3 + number - 1);
+            assert!(wave.enemies.iter().all(|kind| *kind == EnemyKind(0)));
+        }
+    }
+
+    #[test]
+    fn spawns_wait_for_their_tile_and_for_the_head() {
+        let mut simulation = Simulation::new(0, config());
+
+        direct(&mut simulation, 21);
+
+        let spawn = simulation.pending_spawns[0].clone();
+        let stats = simulation.config.enemies[0].clone();
+        let squatter = Enemy::new(
+            EnemyId(100),
+            EnemyKind(0),
+            &stats,
+            spawn.position,
+            &mut simulation.rng,
+        );
+
+        simulation.enemies.push(squatter);
+        simulation.snake.segments[0] = Position {
+            x: spawn.position.x + 1,
+            ..spawn.position
+        };
+        direct(&mut simulation, 200);
+
+        assert!(simulation
+            .pending_spawns
+            .iter()
+            .any(|pending| pending.id == spawn.id));
+
+        simulation.enemies.retain(|enemy| enemy.id != EnemyId(100));
+        direct(&mut simulation, 1);
+
+        assert!(simulation
+            .pending_spawns
+            .iter()
+            .any(|pending| pending.id == spawn.id));
+
+        simulation.snake.segments[0] = Position {
+            x: spawn.position.x + 6,
+            ..spawn.position
+        };
+        let events = direct(&mut simulation, 1);
+
+        assert!(events.contains(&SimulationEvent::EnemySpawned {
+            id: spawn.id,
+            kind: EnemyKind(0),
+        }));
+    }
+}
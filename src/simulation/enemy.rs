@@ -0,0 +1,754 @@
+use glam::Vec2;
+use rand::{seq::SliceRandom, Rng};
+use serde::Deserialize;
+
+use super::{
+    behaviour::{Action, Behaviour, Situation},
+    boss::BossStats,
+    grid::Grid,
+    pathfinding::{distance, find_path, nearest_open},
+    projectile::{Owner, ProjectileStats},
+    snake::{Direction, Snake},
+    Countdown, Position, Simulation, SimulationEvent,
+};
+
+const FLEE_CANDIDATES: usize = 8;
+const FLANK_DISTANCE: i32 = 3;
+/// Damage a bite deals before armor.
+const BITE_DAMAGE: usize = 2;
+/// How many tiles a bitten enemy is thrown back.
+const KNOCKBACK: usize = 2;
+
+fn default_health() -> usize {
+    1
+}
+
+fn default_xp() -> u32 {
+    1
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EnemyId(pub u32);
+
+/// An index into the enemy stats the simulation was created with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EnemyKind(pub usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum AttackKind {
+    /// Hits the tile next to the enemy in the direction it is facing.
+    Melee,
+    /// Fires at a random segment of the snake.
+    Projectile(ProjectileStats),
+    /// Fires `count` projectiles at the head, fanned out `spread` radians
+    /// apart.
+    Volley {
+        projectile: ProjectileStats,
+        count: usize,
+        spread: f32,
+    },
+}
+
+/// Where an enemy heads when it decides to move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum MoveGoal {
+    /// A random open tile.
+    Wander,
+    /// The tile just above the snake's head.
+    Approach,
+    /// The tile furthest from the snake's head out of a few random ones.
+    Flee,
+    /// A tile off to the side of where the snake is heading.
+    Flank,
+    /// The tile above where the snake's head will be in `lead` steps.
+    Intercept { lead: usize },
+}
+
+impl Default for MoveGoal {
+    fn default() -> Self {
+        Self::Wander
+    }
+}
+
+impl MoveGoal {
+    /// Goals that chase the snake are picked again on every step.
+    fn follows_snake(&self) -> bool {
+        matches!(
+            self,
+            MoveGoal::Approach | MoveGoal::Flank | MoveGoal::Intercept { .. }
+        )
+    }
+
+    pub(super) fn target(
+        &self,
+        grid: &Grid,
+        snake: &Snake,
+        direction: Direction,
+        predicted_path: &[Position],
+        rng: &mut impl Rng,
+    ) -> Position {
+        let head = *snake.head();
+
+        let target = match self {
+            MoveGoal::Wander => return grid.random_position(rng),
+            MoveGoal::Approach => Direction::Up.step(head),
+            MoveGoal::Flee => (0..FLEE_CANDIDATES)
+                .map(|_| grid.random_position(rng))
+                .max_by_key(|position| distance(position, &head))
+                .unwrap_or(head),
+            MoveGoal::Flank => {
+                let ahead = (0..FLANK_DISTANCE).fold(head, |position, _| direction.step(position));
+                let side = match (direction, rng.gen::<bool>()) {
+                    (Direction::Up | Direction::Down, true) => Direction::Left,
+                    (Direction::Up | Direction::Down, false) => Direction::Right,
+                    (Direction::Left | Direction::Right, true) => Direction::Up,
+                    (Direction::Left | Direction::Right, false) => Direction::Down,
+                };
+
+                side.step(side.step(ahead))
+            }
+            MoveGoal::Intercept { lead } => {
+                let ahead = predicted_path
+                    .get(lead.saturating_sub(1))
+                    .or_else(|| predicted_path.last())
+                    .copied()
+                    .unwrap_or(head);
+
+                Direction::Up.step(ahead)
+            }
+        };
+
+        nearest_open(grid, |position| snake.contains(position), target).unwrap_or(target)
+    }
+}
+
+/// The gameplay half of an enemy archetype. The sprites and sounds that go
+/// with it live next to it in the asset file but never reach the simulation.
+#[derive(Clone, Debug, Deserialize)]
+pub struct EnemyStats {
+    /// How likely this enemy is to be picked for a wave the director makes
+    /// up once the configured ones run out.
+    pub spawn_weight: u32,
+    /// Seconds an idle enemy waits before deciding what to do next.
+    pub decision_time: (f32, f32),
+    pub behaviour: Behaviour,
+    pub attack: AttackKind,
+    /// Seconds between the wind-up and the attack landing.
+    pub wind_up: f32,
+    pub damage: usize,
+    /// Seconds it takes to move one tile.
+    pub move_step: f32,
+    #[serde(default = "default_health")]
+    pub health: usize,
+    /// Taken off the damage of every bite, though a bite always does at
+    /// least one point.
+    #[serde(default)]
+    pub armor: usize,
+    /// Experience for killing the enemy.
+    #[serde(default = "default_xp")]
+    pub xp: u32,
+    #[serde(default)]
+    pub boss: Option<BossStats>,
+}
+
+impl EnemyStats {
+    pub fn behaviour(&self, phase: usize) -> &Behaviour {
+        match self.boss.as_ref().and_then(|boss| boss.phase(phase)) {
+            Some(phase) => &phase.behaviour,
+            None => &self.behaviour,
+        }
+    }
+
+    pub fn attack(&self, phase: usize) -> AttackKind {
+        match self.boss.as_ref().and_then(|boss| boss.phase(phase)) {
+            Some(phase) => phase.attack,
+            None => self.attack,
+        }
+    }
+
+    pub fn wind_up(&self, phase: usize) -> f32 {
+        match self.boss.as_ref().and_then(|boss| boss.phase(phase)) {
+            Some(phase) => phase.wind_up,
+            None => self.wind_up,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnemyState {
+    Idle,
+    AttackAnimation,
+    Attacking,
+    Moving,
+}
+
+impl EnemyState {
+    pub fn is_attack_animation(&self) -> bool {
+        *self == EnemyState::AttackAnimation
+    }
+
+    pub fn is_attacking(&self) -> bool {
+        *self == EnemyState::Attacking
+    }
+
+    pub fn is_moving(&self) -> bool {
+        *self == EnemyState::Moving
+    }
+
+    pub fn is_idle(&self) -> bool {
+        *self == EnemyState::Idle
+    }
+}
+
+/// Which frame of the enemy's sprite sheet should be shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnemyPose {
+    Idle,
+    FacingRight,
+    Attacking,
+}
+
+#[derive(Clone, Debug)]
+pub struct Enemy {
+    pub id: EnemyId,
+    pub kind: EnemyKind,
+    pub position: Position,
+    pub state: EnemyState,
+    pub pose: EnemyPose,
+    /// Hit points left. The snake only eats an enemy with the bite that
+    /// takes the last of them.
+    pub health: usize,
+    /// The boss phase the enemy is in, 0 for everyone else.
+    pub phase: usize,
+    /// The way melee attacks go, or roughly where shots are aimed.
+    pub facing: Direction,
+    /// The tile a melee attack is about to land on, while winding up.
+    pub telegraph: Option<Position>,
+    /// Where a ranged attack will be fired at, picked when it starts winding
+    /// up.
+    aim: Option<Position>,
+    goal: MoveGoal,
+    target: Option<Position>,
+    decision_timer: Countdown,
+    atk_anim_timer: Countdown,
+    move_step_timer: Countdown,
+}
+
+impl Enemy {
+    pub(super) fn new(
+        id: EnemyId,
+        kind: EnemyKind,
+        stats: &EnemyStats,
+        position: Position,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let mut enemy = Self {
+            id,
+            kind,
+            position,
+            state: EnemyState::Idle,
+            pose: EnemyPose::Idle,
+            health: stats.health,
+            phase: 0,
+            facing: Direction::Down,
+            telegraph: None,
+            aim: None,
+            goal: MoveGoal::default(),
+            target: None,
+            decision_timer: Countdown::once(0.),
+            atk_anim_timer: Countdown::once(0.),
+            move_step_timer: Countdown::repeating(stats.move_step),
+        };
+
+        enemy.reset_decision_timer(stats, rng);
+        enemy.reset_attack_animation_timer(stats);
+        enemy
+    }
+
+    fn reset_decision_timer(&mut self, stats: &EnemyStats, rng: &mut impl Rng) {
+        let (min, max) = stats.decision_time;
+
+        self.decision_timer = Countdown::once(match min < max {
+            true => rng.gen_range(min..max),
+            false => min,
+        });
+    }
+
+    pub(super) fn reset_attack_animation_timer(&mut self, stats: &EnemyStats) {
+        self.atk_anim_timer = Countdown::once(stats.wind_up(self.phase));
+    }
+
+    /// How far along the wind-up of an attack is, from 0 to 1.
+    pub fn wind_up_progress(&self) -> f32 {
+        self.atk_anim_timer.percent()
+    }
+
+    pub(super) fn to_idle(&mut self, stats: &EnemyStats, rng: &mut impl Rng) {
+        self.state = EnemyState::Idle;
+        self.target = None;
+        self.telegraph = None;
+        self.aim = None;
+        self.reset_decision_timer(stats, rng);
+        // An interrupted wind-up starts over the next time
+        self.reset_attack_animation_timer(stats);
+    }
+}
+
+impl Simulation {
+    /// Deals damage that doesn't come from a bite. An enemy that survives it
+    /// drops what it was doing, anything else dies. Returns whether it
+    /// survived.
+    pub(super) fn hurt_enemy(
+        &mut self,
+        index: usize,
+        damage: usize,
+        events: &mut Vec<SimulationEvent>,
+    ) -> bool {
+        let stats = self.config.enemies[self.enemies[index].kind.0].clone();
+        let enemy = &mut self.enemies[index];
+
+        if enemy.health > damage {
+            enemy.health -= damage;
+            events.push(SimulationEvent::EnemyHit {
+                id: enemy.id,
+                position: enemy.position,
+            });
+            enemy.to_idle(&stats, &mut self.rng);
+            self.update_boss_phase(index, events);
+            return true;
+        }
+
+        let enemy = self.enemies.remove(index);
+
+        events.push(SimulationEvent::EnemyKilled {
+            id: enemy.id,
+            position: enemy.position,
+        });
+        self.reward_kill(&enemy, events);
+        false
+    }
+
+    /// Everything that comes with getting rid of an enemy, however it went.
+    pub(super) fn reward_kill(&mut self, enemy: &Enemy, events: &mut Vec<SimulationEvent>) {
+        for _ in 0..1 + self.extra_growth() {
+            self.snake.grow();
+        }
+
+        self.enemies_eaten += 1;
+        self.gain_xp(self.config.enemies[enemy.kind.0].xp, events);
+        self.defeat_boss(enemy, events);
+    }
+
+    /// Bites the enemy the head just moved onto. One with health to spare is
+    /// thrown back along the snake's direction and drops what it was doing.
+    /// Returns whether it survived.
+    pub(super) fn bite_enemy(&mut self, index: usize, events: &mut Vec<SimulationEvent>) -> bool {
+        let stats = self.config.enemies[self.enemies[index].kind.0].clone();
+        let damage = BITE_DAMAGE.saturating_sub(stats.armor).max(1);
+        let enemy = &mut self.enemies[index];
+
+        if enemy.health <= damage {
+            enemy.health = 0;
+            return false;
+        }
+
+        enemy.health -= damage;
+        events.push(SimulationEvent::EnemyHit {
+            id: enemy.id,
+            position: enemy.position,
+        });
+
+        let mut landing = enemy.position;
+
+        for _ in 0..KNOCKBACK {
+            let next = self.direction.step(landing);
+
+            if !self.grid.is_open(&next) || self.snake.contains(&next) {
+                break;
+            }
+
+            landing = next;
+        }
+
+        let enemy = &mut self.enemies[index];
+
+        // Pinned against a wall, it squeezes out wherever there is room
+        enemy.position = match landing == enemy.position {
+            true => nearest_open(
+                &self.grid,
+                |position| self.snake.contains(position),
+                landing,
+            )
+            .unwrap_or(landing),
+            false => landing,
+        };
+        enemy.to_idle(&stats, &mut self.rng);
+
+        self.update_boss_phase(index, events);
+        true
+    }
+
+    pub(super) fn update_enemies(&mut self, events: &mut Vec<SimulationEvent>) {
+        self.decide_enemies(events);
+        self.move_enemies();
+        self.animate_enemy_attacks();
+        self.enemy_attacks(events);
+    }
+
+    fn decide_enemies(&mut self, events: &mut Vec<SimulationEvent>) {
+        // Longer decision times on the difficulty curve run the timers slower
+        let delta = Self::timestep().div_f32(self.difficulty().decision_time.max(0.1));
+        let predicted_path = self.predicted_path();
+        let segments = self.snake.segments.iter().copied().collect::<Vec<_>>();
+
+        for enemy in self.enemies.iter_mut() {
+            if !enemy.state.is_idle() {
+                continue;
+            }
+
+            if !enemy.decision_timer.tick(delta) {
+                continue;
+            }
+
+            let stats = &self.config.enemies[enemy.kind.0];
+            let situation = Situation {
+                position: enemy.position,
+                head: *self.snake.head(),
+                direction: self.direction,
+                length: segments.len(),
+                predicted_path: &predicted_path,
+                segments: &segments,
+            };
+
+            let brain = stats.behaviour(enemy.phase).brain();
+
+            let new_state = match brain.decide(&situation, &mut self.rng) {
+                Action::Wait => EnemyState::Idle,
+                Action::Attack => EnemyState::AttackAnimation,
+                Action::Move(goal) => {
+                    enemy.goal = goal;
+                    EnemyState::Moving
+                }
+            };
+
+            match new_state {
+                EnemyState::Idle => {
+                    enemy.pose = EnemyPose::Idle;
+                    enemy.reset_decision_timer(stats, &mut self.rng);
+                }
+                EnemyState::AttackAnimation => {
+                    // Casters raise their staff while charging, fighters only
+                    // swing once the attack lands but mark where it will
+                    match stats.attack(enemy.phase) {
+                        AttackKind::Projectile(_) => {
+                            let aim = *segments.choose(&mut self.rng).unwrap();
+
+                            enemy.pose = EnemyPose::Attacking;
+                            enemy.facing = Direction::towards(&enemy.position, &aim);
+                            enemy.aim = Some(aim);
+                        }
+                        AttackKind::Volley { .. } => {
+                            enemy.pose = EnemyPose::Attacking;
+                            enemy.facing = Direction::towards(&enemy.position, &situation.head);
+                            enemy.aim = Some(situation.head);
+                        }
+                        AttackKind::Melee => {
+                            enemy.facing = situation.strike_direction();
+                            enemy.telegraph = Some(enemy.facing.step(enemy.position));
+                        }
+                    }
+
+                    events.push(SimulationEvent::EnemyWindUp {
+                        id: enemy.id,
+                        kind: enemy.kind,
+                    });
+                }
+                _ => {}
+            }
+
+            enemy.state = new_state;
+        }
+    }
+
+    fn move_enemies(&mut self) {
+        let delta = Self::timestep();
+        let predicted_path = self.predicted_path();
+
+        for enemy in self.enemies.iter_mut() {
+            if !enemy.state.is_moving() {
+                continue;
+            }
+
+            let stats = &self.config.enemies[enemy.kind.0];
+
+            if enemy.target.is_none() {
+                enemy.target = Some(enemy.goal.target(
+                    &self.grid,
+                    &self.snake,
+                    self.direction,
+                    &predicted_path,
+                    &mut self.rng,
+                ));
+                continue;
+            }
+
+            if !enemy.move_step_timer.tick(delta) {
+                continue;
+            }
+
+            if enemy.goal.follows_snake() {
+                enemy.target = Some(enemy.goal.target(
+                    &self.grid,
+                    &self.snake,
+                    self.direction,
+                    &predicted_path,
+                    &mut self.rng,
+                ));
+            }
+
+            let target = enemy.target.unwrap_or(enemy.position);
+
+            if enemy.position == target {
+                enemy.to_idle(stats, &mut self.rng);
+                continue;
+            }
+
+            let path = find_path(
+                &self.grid,
+                |position| self.snake.contains(position),
+                enemy.position,
+                target,
+            );
+
+            let position = match path.and_then(|path| path.first().copied()) {
+                Some(position) => position,
+                None => {
+                    enemy.to_idle(stats, &mut self.rng);
+                    continue;
+                }
+            };
+
+            if position.x > enemy.position.x {
+                enemy.pose = EnemyPose::FacingRight;
+            } else if position.x < enemy.position.x {
+                enemy.pose = EnemyPose::Idle;
+            }
+
+            enemy.position = position;
+        }
+    }
+
+    fn animate_enemy_attacks(&mut self) {
+        let delta = Self::timestep();
+
+        for enemy in self.enemies.iter_mut() {
+            if !enemy.state.is_attack_animation() {
+                continue;
+            }
+
+            // The attack only lands once the wind-up has played out, and
+            // going back to idle afterwards winds the timer back up
+            if !enemy.atk_anim_timer.tick(delta) {
+                continue;
+            }
+
+            enemy.state = EnemyState::Attacking;
+            enemy.pose = EnemyPose::Idle;
+        }
+    }
+
+    fn enemy_attacks(&mut self, events: &mut Vec<SimulationEvent>) {
+        for index in 0..self.enemies.len() {
+            if self.game_over {
+                return;
+            }
+
+            if !self.enemies[index].state.is_attacking() {
+                continue;
+            }
+
+            let Enemy {
+                id,
+                kind,
+                position,
+                facing,
+                phase,
+                aim,
+                ..
+            } = self.enemies[index];
+            let stats = self.config.enemies[kind.0].clone();
+            let from = Vec2::new(position.x as f32, position.y as f32);
+            let aim_at = |target: &Position| {
+                (Vec2::new(target.x as f32, target.y as f32) - from).normalize_or_zero()
+            };
+
+            let damage = self.enemy_damage(stats.damage);
+
+            match stats.attack(phase) {
+                AttackKind::Projectile(projectile) => {
+                    let direction = aim_at(&aim.unwrap_or(*self.snake.head()));
+
+                    self.fire_projectile(
+                        position,
+                        direction,
+                        projectile,
+                        damage,
+                        Owner::Enemy,
+                        events,
+                    );
+                }
+                AttackKind::Volley {
+                    projectile,
+                    count,
+                    spread,
+                } => {
+                    let direction = aim_at(&aim.unwrap_or(*self.snake.head()));
+
+                    for shot in 0..count {
+                        let angle = (shot as f32 - (count as f32 - 1.) / 2.) * spread;
+                        let direction = Vec2::from_angle(angle).rotate(direction);
+
+                        self.fire_projectile(
+                            position,
+                            direction,
+                            projectile,
+                            damage,
+                            Owner::Enemy,
+                            events,
+                        );
+                    }
+                }
+                AttackKind::Melee => {
+                    self.enemies[index].pose = EnemyPose::Attacking;
+
+                    let target = facing.step(position);
+
+                    if self.snake.contains(&target) {
+                        self.damage_snake(damage, target, events);
+                    }
+                }
+            }
+
+            events.push(SimulationEvent::EnemyAttacked { id, kind });
+            self.enemies[index].to_idle(&stats, &mut self.rng);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::SimulationConfig;
+
+    const ENEMIES: &str = r#"#![enable(unwrap_variant_newtypes)]
+[
+    (
+        spawn_weight: 1,
+        decision_time: (1.0, 1.0),
+        behaviour: Ambusher(lead: 4),
+        attack: Melee,
+        wind_up: 0.5,
+        damage: 1,
+        move_step: 0.25,
+        health: 3,
+    ),
+]"#;
+
+    const BOSS: &str = r#"#![enable(unwrap_variant_newtypes)]
+[
+    (
+        spawn_weight: 0,
+        decision_time: (1.0, 1.0),
+        behaviour: Ambusher(lead: 4),
+        attack: Melee,
+        wind_up: 0.5,
+        damage: 1,
+        move_step: 0.25,
+        health: 4,
+        boss: Some((
+            score: 10,
+            reward: 3,
+            phases: [
+                (below: 0.75, behaviour: Ambusher(lead: 2), attack: Melee, wind_up: 1.0),
+            ],
+        )),
+    ),
+]"#;
+
+    /// Ticks a 0.5 second wind-up takes.
+    const WIND_UP_TICKS: usize = 20;
+
+    /// A run with one enemy in it, already winding up an attack.
+    fn simulation(enemies: &str) -> Simulation {
+        let mut simulation = Simulation::new(
+            0,
+            SimulationConfig {
+                enemies: ron::from_str(enemies).unwrap(),
+                ..Default::default()
+            },
+        );
+        let stats = simulation.config.enemies[0].clone();
+        let mut enemy = Enemy::new(
+            EnemyId(0),
+            EnemyKind(0),
+            &stats,
+            Position { x: 6, y: 6 },
+            &mut simulation.rng,
+        );
+
+        enemy.state = EnemyState::AttackAnimation;
+        simulation.enemies = vec![enemy];
+        simulation
+    }
+
+    fn ticks_until_attack(simulation: &mut Simulation) -> usize {
+        (1..100)
+            .find(|_| {
+                simulation.animate_enemy_attacks();
+                simulation.enemies[0].state.is_attacking()
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn interrupted_wind_ups_start_over() {
+        let mut simulation = simulation(ENEMIES);
+        let stats = simulation.config.enemies[0].clone();
+
+        for _ in 0..WIND_UP_TICKS / 2 {
+            simulation.animate_enemy_attacks();
+        }
+
+        simulation.enemies[0].to_idle(&stats, &mut simulation.rng);
+        simulation.enemies[0].state = EnemyState::AttackAnimation;
+
+        assert_eq!(ticks_until_attack(&mut simulation), WIND_UP_TICKS);
+    }
+
+    #[test]
+    fn boss_phases_bring_their_own_wind_up() {
+        let mut simulation = simulation(BOSS);
+
+        assert!(simulation.hurt_enemy(0, 1, &mut Vec::new()));
+        assert_eq!(simulation.enemies[0].phase, 1);
+
+        simulation.enemies[0].state = EnemyState::AttackAnimation;
+
+        assert_eq!(ticks_until_attack(&mut simulation), WIND_UP_TICKS * 2);
+    }
+
+    #[test]
+    fn knocked_back_enemies_wind_up_from_the_start() {
+        let mut simulation = simulation(ENEMIES);
+
+        for _ in 0..WIND_UP_TICKS / 2 {
+            simulation.animate_enemy_attacks();
+        }
+
+        assert!(simulation.bite_enemy(0, &mut Vec::new()));
+
+        simulation.enemies[0].state = EnemyState::AttackAnimation;
+
+        assert_eq!(ticks_until_attack(&mut simulation), WIND_UP_TICKS);
+    }
+}
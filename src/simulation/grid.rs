@@ -0,0 +1,129 @@
+use glam::IVec2;
+use rand::{seq::SliceRandom, Rng};
+
+use super::Position;
+
+/// The size of the open arena used when no level file is loaded.
+pub const DEFAULT_LEVEL_SIZE: IVec2 = IVec2::new(31, 23);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tile {
+    Floor,
+    Wall,
+    /// Swallows anything walking into it, but projectiles fly over it.
+    Pit,
+    /// Blocks like a wall until a projectile breaks it.
+    Crate,
+}
+
+/// The tiles of a level, centered on the origin so the camera doesn't have
+/// to know about the level's size.
+#[derive(Clone, Debug)]
+pub struct Grid {
+    pub size: IVec2,
+    tiles: Vec<Tile>,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self::new(DEFAULT_LEVEL_SIZE)
+    }
+}
+
+impl Grid {
+    /// An arena of the given size with nothing but floor.
+    pub fn new(size: IVec2) -> Self {
+        Self {
+            size,
+            tiles: vec![Tile::Floor; (size.x * size.y) as usize],
+        }
+    }
+
+    /// The bottom left tile.
+    pub fn min(&self) -> Position {
+        Position {
+            x: -(self.size.x - 1) / 2,
+            y: -(self.size.y - 1) / 2,
+        }
+    }
+
+    /// The top right tile.
+    pub fn max(&self) -> Position {
+        let min = self.min();
+
+        Position {
+            x: min.x + self.size.x - 1,
+            y: min.y + self.size.y - 1,
+        }
+    }
+
+    pub fn contains(&self, position: &Position) -> bool {
+        let (min, max) = (self.min(), self.max());
+
+        position.x >= min.x && position.x <= max.x && position.y >= min.y && position.y <= max.y
+    }
+
+    /// The tile inside the grid closest to `position`.
+    pub fn clamp(&self, position: Position) -> Position {
+        let (min, max) = (self.min(), self.max());
+
+        Position {
+            x: position.x.clamp(min.x, max.x),
+            y: position.y.clamp(min.y, max.y),
+        }
+    }
+
+    /// Where the tile is stored, for code that keeps its own per tile data.
+    pub(super) fn index(&self, position: &Position) -> Option<usize> {
+        if !self.contains(position) {
+            return None;
+        }
+
+        let min = self.min();
+
+        Some(((position.y - min.y) * self.size.x + position.x - min.x) as usize)
+    }
+
+    pub fn tile(&self, position: &Position) -> Option<Tile> {
+        self.index(position).map(|index| self.tiles[index])
+    }
+
+    pub fn set_tile(&mut self, position: &Position, tile: Tile) {
+        if let Some(index) = self.index(position) {
+            self.tiles[index] = tile;
+        }
+    }
+
+    /// Whether the snake or an enemy can stand on the tile.
+    pub fn is_open(&self, position: &Position) -> bool {
+        self.tile(position) == Some(Tile::Floor)
+    }
+
+    /// Whether a projectile flying into the tile stops there.
+    pub fn blocks_projectiles(&self, position: &Position) -> bool {
+        match self.tile(position) {
+            Some(Tile::Floor) | Some(Tile::Pit) => false,
+            Some(Tile::Wall) | Some(Tile::Crate) | None => true,
+        }
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Position> {
+        let (min, max) = (self.min(), self.max());
+
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Position { x, y }))
+    }
+
+    pub fn open_positions(&self) -> Vec<Position> {
+        self.positions()
+            .filter(|position| self.is_open(position))
+            .collect()
+    }
+
+    /// A random open tile, or the center of the level if there is none.
+    pub fn random_position(&self, rng: &mut impl Rng) -> Position {
+        self.open_positions()
+            .choose(rng)
+            .copied()
+            .unwrap_or(Position { x: 0, y: 0 })
+    }
+}
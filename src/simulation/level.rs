@@ -0,0 +1,174 @@
+use glam::IVec2;
+use serde::Deserialize;
+
+use super::{
+    grid::{Grid, Tile},
+    snake::Direction,
+    Position,
+};
+
+/// A level as it is written in a `.level.ron` file. The layout is drawn top
+/// row first, one character per tile:
+///
+/// - `.` floor
+/// - `#` wall
+/// - `x` pit
+/// - `c` crate, which projectiles can break
+/// - `S` a spot the snake's head can start on
+/// - `e` floor enemies are allowed to spawn on; without any, they can spawn
+///   on every open tile
+#[derive(Clone, Debug, Deserialize)]
+pub struct LevelDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub start_direction: Direction,
+    pub layout: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "LevelDefinition")]
+pub struct Level {
+    pub name: String,
+    pub grid: Grid,
+    pub spawn_points: Vec<Position>,
+    pub enemy_spawns: Vec<Position>,
+    pub start_direction: Direction,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self {
+            name: "Arena".to_string(),
+            grid: Grid::default(),
+            spawn_points: vec![Position { x: 0, y: 0 }],
+            enemy_spawns: Vec::new(),
+            start_direction: Direction::default(),
+        }
+    }
+}
+
+impl TryFrom<LevelDefinition> for Level {
+    type Error = String;
+
+    fn try_from(definition: LevelDefinition) -> Result<Self, Self::Error> {
+        let height = definition.layout.len() as i32;
+        let width = definition
+            .layout
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or_default() as i32;
+
+        if width == 0 || height == 0 {
+            return Err(format!("Level {} has an empty layout", definition.name));
+        }
+
+        let mut grid = Grid::new(IVec2::new(width, height));
+        let mut spawn_points = Vec::new();
+        let mut enemy_spawns = Vec::new();
+        let (min, max) = (grid.min(), grid.max());
+
+        for (row, line) in definition.layout.iter().enumerate() {
+            for (column, character) in line.chars().enumerate() {
+                let position = Position {
+                    x: min.x + column as i32,
+                    y: max.y - row as i32,
+                };
+
+                match character {
+                    '.' => {}
+                    '#' => grid.set_tile(&position, Tile::Wall),
+                    'x' => grid.set_tile(&position, Tile::Pit),
+                    'c' => grid.set_tile(&position, Tile::Crate),
+                    'S' => spawn_points.push(position),
+                    'e' => enemy_spawns.push(position),
+                    _ => {
+                        return Err(format!(
+                            "Unknown tile '{}' at row {}, column {} of level {}",
+                            character,
+                            row + 1,
+                            column + 1,
+                            definition.name
+                        ))
+                    }
+                }
+            }
+        }
+
+        if spawn_points.is_empty() {
+            return Err(format!("Level {} has no spawn point", definition.name));
+        }
+
+        // The body trails behind the head, so that has to be open too
+        let behind = definition.start_direction.opposite();
+
+        for spawn_point in spawn_points.iter() {
+            let tail = behind.step(behind.step(*spawn_point));
+
+            if !grid.is_open(&behind.step(*spawn_point)) || !grid.is_open(&tail) {
+                return Err(format!(
+                    "Spawn point ({}, {}) of level {} has no room for the snake",
+                    spawn_point.x, spawn_point.y, definition.name
+                ));
+            }
+        }
+
+        Ok(Self {
+            name: definition.name,
+            grid,
+            spawn_points,
+            enemy_spawns,
+            start_direction: definition.start_direction,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(layout: &[&str]) -> Result<Level, String> {
+        Level::try_from(LevelDefinition {
+            name: "Test".to_string(),
+            start_direction: Direction::Right,
+            layout: layout.iter().map(|row| row.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn layouts_become_grids_with_spawns() {
+        let level = level(&["#####", "#..S#", "#e.c#", "#####"]).unwrap();
+
+        assert_eq!(level.spawn_points.len(), 1);
+        assert_eq!(level.enemy_spawns.len(), 1);
+        assert!(level.grid.is_open(&level.spawn_points[0]));
+        assert!(!level
+            .grid
+            .is_open(&Direction::Down.step(level.spawn_points[0])));
+    }
+
+    #[test]
+    fn empty_layouts_are_refused() {
+        assert!(level(&[]).is_err());
+        assert!(level(&["", ""]).is_err());
+    }
+
+    #[test]
+    fn unknown_tiles_are_refused() {
+        let error = level(&["..S", "..?"]).unwrap_err();
+
+        assert!(error.contains("'?' at row 2, column 3"), "{}", error);
+    }
+
+    #[test]
+    fn layouts_need_a_spawn_point() {
+        assert!(level(&["...", "..."]).is_err());
+    }
+
+    #[test]
+    fn spawn_points_need_room_behind_them() {
+        assert!(level(&["..S."]).is_ok());
+        assert!(level(&["#.S."]).is_err());
+        assert!(level(&[".S.."]).is_err());
+    }
+}
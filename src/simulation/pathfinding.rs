@@ -0,0 +1,231 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+};
+
+use super::{grid::Grid, Position};
+
+const NEIGHBOURS: [(i32, i32); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/// The open tiles an enemy can step to from `position`. Diagonal steps need
+/// both tiles beside them to be open, so nobody squeezes between two walls.
+fn neighbours<'a>(
+    grid: &'a Grid,
+    blocked: &'a impl Fn(&Position) -> bool,
+    position: Position,
+) -> impl Iterator<Item = Position> + 'a {
+    let walkable = move |position: &Position| grid.is_open(position) && !blocked(position);
+
+    NEIGHBOURS.into_iter().filter_map(move |(x, y)| {
+        let next = Position {
+            x: position.x + x,
+            y: position.y + y,
+        };
+
+        let diagonal_clear = x == 0
+            || y == 0
+            || (walkable(&Position {
+                x: next.x,
+                ..position
+            }) && walkable(&Position {
+                y: next.y,
+                ..position
+            }));
+
+        match diagonal_clear && walkable(&next) {
+            true => Some(next),
+            false => None,
+        }
+    })
+}
+
+/// Moving diagonally costs the same as moving straight, so the number of
+/// steps between two tiles is their Chebyshev distance.
+pub fn distance(from: &Position, to: &Position) -> i32 {
+    (from.x - to.x).abs().max((from.y - to.y).abs())
+}
+
+/// Finds the shortest walk from `from` to `to` with A*, skipping tiles the
+/// grid or `blocked` rule out. The path leaves out `from` and ends on `to`.
+pub fn find_path(
+    grid: &Grid,
+    blocked: impl Fn(&Position) -> bool,
+    from: Position,
+    to: Position,
+) -> Option<Vec<Position>> {
+    // Tiles are queued by their index in `found`, which also breaks ties
+    // between equally good tiles by the order they were found and keeps the
+    // search deterministic
+    let mut found = vec![from];
+    let mut open = BinaryHeap::from([Reverse((distance(&from, &to), 0))]);
+    let mut came_from = HashMap::new();
+    let mut cost = HashMap::from([(from, 0)]);
+
+    while let Some(Reverse((_, index))) = open.pop() {
+        let position = found[index];
+
+        if position == to {
+            let mut path = vec![to];
+
+            while let Some(previous) = came_from.get(path.last().unwrap()) {
+                if *previous == from {
+                    break;
+                }
+
+                path.push(*previous);
+            }
+
+            path.reverse();
+            return Some(path);
+        }
+
+        let next_cost = cost[&position] + 1;
+
+        for next in neighbours(grid, &blocked, position) {
+            if cost.get(&next).map_or(false, |known| *known <= next_cost) {
+                continue;
+            }
+
+            cost.insert(next, next_cost);
+            came_from.insert(next, position);
+            found.push(next);
+            open.push(Reverse((next_cost + distance(&next, &to), found.len() - 1)));
+        }
+    }
+
+    None
+}
+
+/// Finds the walkable tile closest to `target` with a breadth first search
+/// out from it, for goals that land on a wall or the snake.
+pub fn nearest_open(
+    grid: &Grid,
+    blocked: impl Fn(&Position) -> bool,
+    target: Position,
+) -> Option<Position> {
+    let target = grid.clamp(target);
+    let mut queue = VecDeque::from([target]);
+    let mut seen = HashSet::from([target]);
+
+    while let Some(position) = queue.pop_front() {
+        if grid.is_open(&position) && !blocked(&position) {
+            return Some(position);
+        }
+
+        for (x, y) in NEIGHBOURS {
+            let next = Position {
+                x: position.x + x,
+                y: position.y + y,
+            };
+
+            if grid.contains(&next) && seen.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::IVec2;
+
+    use super::*;
+    use crate::simulation::grid::Tile;
+
+    fn position(x: i32, y: i32) -> Position {
+        Position { x, y }
+    }
+
+    /// A 9x9 room with a wall down the middle, open only at the top row.
+    fn walled_grid() -> Grid {
+        let mut grid = Grid::new(IVec2::new(9, 9));
+
+        for y in -4..4 {
+            grid.set_tile(&position(0, y), Tile::Wall);
+        }
+
+        grid
+    }
+
+    fn assert_walkable(grid: &Grid, from: Position, path: &[Position]) {
+        let mut previous = from;
+
+        for step in path {
+            assert_eq!(distance(&previous, step), 1);
+            assert!(grid.is_open(step));
+            previous = *step;
+        }
+    }
+
+    #[test]
+    fn paths_take_diagonals_in_the_open() {
+        let grid = Grid::new(IVec2::new(9, 9));
+        let path = find_path(&grid, |_| false, position(-3, -3), position(2, 0)).unwrap();
+
+        assert_eq!(path.len(), 5);
+        assert_eq!(path.last(), Some(&position(2, 0)));
+        assert_walkable(&grid, position(-3, -3), &path);
+    }
+
+    #[test]
+    fn paths_go_around_walls() {
+        let grid = walled_grid();
+        let path = find_path(&grid, |_| false, position(-1, -4), position(1, -4)).unwrap();
+
+        // Up to the gap in the top row and back down the other side
+        assert_eq!(path.len(), 18);
+        assert!(path.contains(&position(0, 4)));
+        assert_walkable(&grid, position(-1, -4), &path);
+    }
+
+    #[test]
+    fn paths_do_not_squeeze_between_walls() {
+        let mut grid = Grid::new(IVec2::new(9, 9));
+        grid.set_tile(&position(1, 0), Tile::Wall);
+        grid.set_tile(&position(0, 1), Tile::Wall);
+
+        let path = find_path(&grid, |_| false, position(0, 0), position(1, 1)).unwrap();
+
+        // The gap is too tight to cut through, so it goes all the way around
+        // one of the walls
+        assert_eq!(path.len(), 6);
+        assert_walkable(&grid, position(0, 0), &path);
+    }
+
+    #[test]
+    fn blocked_targets_have_no_path() {
+        let grid = walled_grid();
+        let blocked = |position: &Position| position.x == 0;
+
+        assert_eq!(
+            find_path(&grid, blocked, position(-1, 0), position(1, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn nearest_open_steps_off_walls() {
+        let grid = walled_grid();
+
+        assert_eq!(
+            nearest_open(&grid, |_| false, position(0, 0))
+                .map(|open| distance(&open, &position(0, 0))),
+            Some(1)
+        );
+        assert_eq!(
+            nearest_open(&grid, |_| false, position(0, 40)),
+            Some(position(0, 4))
+        );
+    }
+}
@@ -0,0 +1,396 @@
+//! Things lying around the grid for the snake to grab. Pickups turn up every
+//! so often away from the head, vanish again if left alone for too long, and
+//! either act right away or hand out a boost that wears off.
+
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+use super::{pathfinding::distance, Countdown, Position, Simulation, SimulationEvent};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PickupId(pub u32);
+
+/// An index into the pickup kinds the simulation was created with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PickupKind(pub usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum PickupEffect {
+    /// Grows the snake by `growth` segments.
+    Food { growth: usize },
+    /// Soaks up the next hit the snake takes.
+    Shield,
+    /// Takes `ticks` off the time between snake steps for a while, or adds
+    /// them if negative.
+    Speed { ticks: i64, duration: f32 },
+    /// Enemies and projectiles move at half speed for a while.
+    SlowTime { duration: f32 },
+    /// Pickups within `range` of the head are grabbed for a while.
+    Magnet { range: i32, duration: f32 },
+    /// Deals `damage` to every enemy within `radius` of the pickup.
+    Bomb { radius: i32, damage: usize },
+}
+
+impl PickupEffect {
+    /// How long the boost lasts, for effects that wear off.
+    fn duration(&self) -> Option<f32> {
+        match self {
+            Self::Speed { duration, .. }
+            | Self::SlowTime { duration }
+            | Self::Magnet { duration, .. } => Some(*duration),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PickupStats {
+    /// How likely this kind is to be picked for a spawn.
+    pub weight: u32,
+    /// Seconds the pickup stays on the grid.
+    pub lifetime: f32,
+    pub effect: PickupEffect,
+}
+
+#[derive(Clone, Debug)]
+pub struct PickupConfig {
+    /// Seconds between two pickups turning up.
+    pub interval: f32,
+    /// How many pickups can lie around at once.
+    pub max_out: usize,
+    /// Pickups never turn up closer than this to the snake's head.
+    pub min_distance: i32,
+    pub kinds: Vec<PickupStats>,
+}
+
+impl Default for PickupConfig {
+    fn default() -> Self {
+        Self {
+            interval: 6.,
+            max_out: 3,
+            min_distance: 4,
+            kinds: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Pickup {
+    pub id: PickupId,
+    pub kind: PickupKind,
+    pub position: Position,
+    lifetime: Countdown,
+}
+
+impl Pickup {
+    /// How much of its lifetime the pickup has used up, from 0 to 1.
+    pub fn age(&self) -> f32 {
+        self.lifetime.percent()
+    }
+}
+
+/// An effect from a pickup that hasn't worn off yet.
+#[derive(Clone, Debug)]
+pub struct Boost {
+    pub kind: PickupKind,
+    timer: Countdown,
+}
+
+impl Boost {
+    /// How much of the boost is used up, from 0 to 1.
+    pub fn age(&self) -> f32 {
+        self.timer.percent()
+    }
+}
+
+impl Simulation {
+    pub fn boosts(&self) -> &[Boost] {
+        &self.boosts
+    }
+
+    fn boost_effects(&self) -> impl Iterator<Item = PickupEffect> + '_ {
+        self.boosts
+            .iter()
+            .map(|boost| self.config.pickups.kinds[boost.kind.0].effect)
+    }
+
+    /// Ticks taken off the time between snake steps by speed boosts.
+    pub(super) fn boost_haste(&self) -> i64 {
+        self.boost_effects()
+            .map(|effect| match effect {
+                PickupEffect::Speed { ticks, .. } => ticks,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    pub fn is_time_slowed(&self) -> bool {
+        self.boost_effects()
+            .any(|effect| matches!(effect, PickupEffect::SlowTime { .. }))
+    }
+
+    /// How far from the head pickups are grabbed, counting magnet upgrades
+    /// and the strongest magnet boost.
+    pub fn magnet_range(&self) -> i32 {
+        let boost = self
+            .boost_effects()
+            .filter_map(|effect| match effect {
+                PickupEffect::Magnet { range, .. } => Some(range),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+
+        self.magnet_bonus() as i32 + boost
+    }
+
+    /// Spawns, ages and wears off everything pickup related.
+    pub(super) fn update_pickups(&mut self, events: &mut Vec<SimulationEvent>) {
+        let delta = Self::timestep();
+
+        self.pickups
+            .retain_mut(|pickup| !pickup.lifetime.tick(delta));
+        self.boosts.retain_mut(|boost| !boost.timer.tick(delta));
+
+        if !self.pickup_timer.tick(delta)
+            || self.pickups.len() >= self.config.pickups.max_out
+            || self.config.pickups.kinds.is_empty()
+        {
+            return;
+        }
+
+        let kinds = (0..self.config.pickups.kinds.len()).collect::<Vec<_>>();
+        let kind = match kinds.choose_weighted(&mut self.rng, |index| {
+            self.config.pickups.kinds[*index].weight
+        }) {
+            Ok(index) => PickupKind(*index),
+            Err(_) => return,
+        };
+        let position = match self.pickup_position() {
+            Some(position) => position,
+            None => return,
+        };
+        let id = PickupId(self.next_id());
+
+        self.pickups.push(Pickup {
+            id,
+            kind,
+            position,
+            lifetime: Countdown::once(self.config.pickups.kinds[kind.0].lifetime),
+        });
+        events.push(SimulationEvent::PickupSpawned { id, kind });
+    }
+
+    /// A free tile at least `min_distance` from the head.
+    fn pickup_position(&mut self) -> Option<Position> {
+        let head = *self.snake.head();
+        let free = self
+            .grid
+            .open_positions()
+            .into_iter()
+            .filter(|position| {
+                distance(position, &head) >= self.config.pickups.min_distance
+                    && !self.snake.contains(position)
+                    && !self.enemies.iter().any(|enemy| enemy.position == *position)
+                    && !self
+                        .pickups
+                        .iter()
+                        .any(|pickup| pickup.position == *position)
+            })
+            .collect::<Vec<_>>();
+
+        free.choose(&mut self.rng).copied()
+    }
+
+    /// Grabs every pickup under the head or within reach of a magnet.
+    pub(super) fn collect_pickups(&mut self, events: &mut Vec<SimulationEvent>) {
+        if self.game_over {
+            return;
+        }
+
+        let head = *self.snake.head();
+        let range = self.magnet_range();
+        let mut index = 0;
+
+        while index < self.pickups.len() {
+            if distance(&self.pickups[index].position, &head) > range {
+                index += 1;
+                continue;
+            }
+
+            let Pickup {
+                id, kind, position, ..
+            } = self.pickups.remove(index);
+
+            events.push(SimulationEvent::PickupCollected { id, kind, position });
+            self.apply_pickup(kind, position, events);
+        }
+    }
+
+    fn apply_pickup(
+        &mut self,
+        kind: PickupKind,
+        position: Position,
+        events: &mut Vec<SimulationEvent>,
+    ) {
+        let effect = self.config.pickups.kinds[kind.0].effect;
+
+        if let Some(duration) = effect.duration() {
+            // Grabbing a boost that is still running starts it over
+            self.boosts.retain(|boost| boost.kind != kind);
+            self.boosts.push(Boost {
+                kind,
+                timer: Countdown::once(duration),
+            });
+            return;
+        }
+
+        match effect {
+            PickupEffect::Food { growth } => {
+                for _ in 0..growth {
+                    self.snake.grow();
+                }
+            }
+            PickupEffect::Shield => self.snake.shielded = true,
+            PickupEffect::Bomb { radius, damage } => {
+                events.push(SimulationEvent::BombExploded { position, radius });
+
+                let mut index = 0;
+
+                while index < self.enemies.len() {
+                    if distance(&self.enemies[index].position, &position) > radius
+                        || self.hurt_enemy(index, damage, events)
+                    {
+                        index += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::SimulationConfig;
+
+    fn simulation(effect: PickupEffect) -> Simulation {
+        Simulation::new(
+            0,
+            SimulationConfig {
+                pickups: PickupConfig {
+                    interval: 0.75,
+                    kinds: vec![PickupStats {
+                        weight: 1,
+                        lifetime: 1.,
+                        effect,
+                    }],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Drops a pickup `offset` tiles to the right of the head.
+    fn drop_pickup(simulation: &mut Simulation, offset: i32) -> PickupId {
+        let head = *simulation.snake.head();
+        let id = PickupId(simulation.next_id());
+
+        simulation.pickups.push(Pickup {
+            id,
+            kind: PickupKind(0),
+            position: Position {
+                x: head.x + offset,
+                ..head
+            },
+            lifetime: Countdown::once(1.),
+        });
+        id
+    }
+
+    fn update(simulation: &mut Simulation, ticks: usize) -> Vec<SimulationEvent> {
+        let mut events = Vec::new();
+
+        for _ in 0..ticks {
+            simulation.update_pickups(&mut events);
+        }
+
+        events
+    }
+
+    #[test]
+    fn pickups_turn_up_away_from_the_head_and_vanish() {
+        let mut simulation = simulation(PickupEffect::Shield);
+        let head = *simulation.snake.head();
+        let events = update(&mut simulation, 30);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(simulation.pickups.len(), 1);
+        assert!(distance(&simulation.pickups[0].position, &head) >= 4);
+
+        // The second one turns up before the first runs out
+        update(&mut simulation, 39);
+
+        assert_eq!(simulation.pickups.len(), 2);
+
+        update(&mut simulation, 1);
+
+        assert_eq!(simulation.pickups.len(), 1);
+    }
+
+    #[test]
+    fn shields_are_put_up_on_the_spot() {
+        let mut simulation = simulation(PickupEffect::Shield);
+
+        drop_pickup(&mut simulation, 0);
+        simulation.collect_pickups(&mut Vec::new());
+
+        assert!(simulation.snake.shielded);
+        assert!(simulation.pickups.is_empty());
+    }
+
+    #[test]
+    fn food_grows_the_snake() {
+        let mut simulation = simulation(PickupEffect::Food { growth: 2 });
+        let mut events = Vec::new();
+        let id = drop_pickup(&mut simulation, 0);
+        let length = simulation.snake.segments.len();
+
+        simulation.collect_pickups(&mut events);
+        simulation.snake.advance(simulation.direction);
+        simulation.snake.advance(simulation.direction);
+
+        assert!(events.iter().any(|event| matches!(
+            event,
+            SimulationEvent::PickupCollected { id: collected, .. } if *collected == id
+        )));
+        assert_eq!(simulation.snake.segments.len(), length + 2);
+    }
+
+    #[test]
+    fn magnet_boosts_reach_further_until_they_wear_off() {
+        let mut simulation = simulation(PickupEffect::Magnet {
+            range: 3,
+            duration: 0.5,
+        });
+        let mut events = Vec::new();
+
+        drop_pickup(&mut simulation, 0);
+        let far = drop_pickup(&mut simulation, 3);
+        simulation.collect_pickups(&mut events);
+
+        assert_eq!(simulation.magnet_range(), 3);
+        assert!(simulation.pickups.iter().any(|pickup| pickup.id == far));
+
+        simulation.collect_pickups(&mut events);
+
+        assert!(simulation.pickups.is_empty());
+
+        update(&mut simulation, 20);
+
+        assert!(simulation.boosts().is_empty());
+        assert_eq!(simulation.magnet_range(), 0);
+    }
+}
@@ -0,0 +1,354 @@
+use std::cmp::Ordering;
+
+use glam::Vec2;
+use rand::Rng;
+use serde::Deserialize;
+
+use super::{enemy::EnemyId, grid::Tile, Countdown, Position, Simulation, SimulationEvent};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProjectileId(pub u32);
+
+fn default_lifetime() -> f32 {
+    5.
+}
+
+fn default_size() -> f32 {
+    0.5
+}
+
+/// How a projectile flies, as written in the stats of whoever fires it.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct ProjectileStats {
+    /// Tiles per second.
+    pub speed: f32,
+    /// Seconds before the projectile fizzles out.
+    #[serde(default = "default_lifetime")]
+    pub lifetime: f32,
+    /// How many segments the projectile passes through before it stops.
+    #[serde(default)]
+    pub pierce: usize,
+    /// Radians per second the projectile turns towards its target: the
+    /// snake's head, or the nearest enemy for the snake's own shots.
+    #[serde(default)]
+    pub homing: f32,
+    /// The width of the projectile in tiles.
+    #[serde(default = "default_size")]
+    pub size: f32,
+}
+
+/// Who fired a projectile, and so what it can hurt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Owner {
+    Enemy,
+    Snake,
+}
+
+#[derive(Clone, Debug)]
+pub struct Projectile {
+    pub id: ProjectileId,
+    pub position: Vec2,
+    pub direction: Vec2,
+    pub size: f32,
+    pub owner: Owner,
+    stats: ProjectileStats,
+    damage: usize,
+    lifetime: Countdown,
+    pierce_left: usize,
+    /// Tiles already damaged, so a piercing shot doesn't hit one twice.
+    hit: Vec<Position>,
+}
+
+impl Projectile {
+    pub fn tile(&self) -> Position {
+        Position::from(self.position + Vec2::splat(0.5))
+    }
+
+    fn home_in(&mut self, target: Position, delta: f32) {
+        let wanted =
+            (Vec2::new(target.x as f32, target.y as f32) - self.position).normalize_or_zero();
+
+        if self.stats.homing <= 0. || wanted == Vec2::ZERO {
+            return;
+        }
+
+        let turn = self.stats.homing * delta;
+        let angle = self.direction.angle_between(wanted).clamp(-turn, turn);
+
+        self.direction = Vec2::from_angle(angle).rotate(self.direction);
+    }
+}
+
+/// When a box of half width `radius` moving from `from` by `motion` first
+/// touches `tile`, as a fraction of the move.
+fn entry(from: Vec2, motion: Vec2, tile: Position, radius: f32) -> Option<f32> {
+    let (mut enter, mut exit) = (0f32, 1f32);
+
+    for (start, moved, center) in [
+        (from.x, motion.x, tile.x as f32),
+        (from.y, motion.y, tile.y as f32),
+    ] {
+        let (low, high) = (center - 0.5 - radius, center + 0.5 + radius);
+
+        if moved.abs() <= f32::EPSILON {
+            if start < low || start >= high {
+                return None;
+            }
+
+            continue;
+        }
+
+        let (a, b) = ((low - start) / moved, (high - start) / moved);
+        enter = enter.max(a.min(b));
+        exit = exit.min(a.max(b));
+    }
+
+    match enter < exit {
+        true => Some(enter),
+        false => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Contact {
+    /// The box of the projectile overlaps a tile, which can hit whatever is
+    /// standing on it.
+    Body,
+    /// The center of the projectile enters a tile, which can stop it.
+    Center,
+}
+
+/// Every tile the projectile touches on its way from `from` by `motion`, in
+/// the order it touches them. Walls are only checked against the center so
+/// a wide shot doesn't snag on corners.
+fn sweep(from: Vec2, motion: Vec2, radius: f32) -> Vec<(f32, Position, Contact)> {
+    let to = from + motion;
+    let low = from.min(to) - Vec2::splat(radius + 0.5);
+    let high = from.max(to) + Vec2::splat(radius + 0.5);
+    let mut contacts = Vec::new();
+
+    for y in low.y.floor() as i32..=high.y.ceil() as i32 {
+        for x in low.x.floor() as i32..=high.x.ceil() as i32 {
+            let tile = Position { x, y };
+
+            if let Some(time) = entry(from, motion, tile, radius) {
+                contacts.push((time, tile, Contact::Body));
+            }
+
+            if let Some(time) = entry(from, motion, tile, 0.) {
+                contacts.push((time, tile, Contact::Center));
+            }
+        }
+    }
+
+    contacts.sort_by(|(a, a_tile, _), (b, b_tile, _)| {
+        a.partial_cmp(b)
+            .unwrap_or(Ordering::Equal)
+            .then((a_tile.y, a_tile.x).cmp(&(b_tile.y, b_tile.x)))
+    });
+    contacts
+}
+
+impl Simulation {
+    pub(super) fn fire_projectile(
+        &mut self,
+        from: Position,
+        direction: Vec2,
+        mut stats: ProjectileStats,
+        damage: usize,
+        owner: Owner,
+        events: &mut Vec<SimulationEvent>,
+    ) {
+        if owner == Owner::Enemy {
+            stats.speed *= self.difficulty().projectile_speed;
+        }
+
+        let position = Vec2::new(from.x as f32, from.y as f32);
+        let id = ProjectileId(self.next_id());
+
+        self.projectiles.push(Projectile {
+            id,
+            position,
+            direction,
+            size: stats.size,
+            owner,
+            stats,
+            damage,
+            lifetime: Countdown::once(stats.lifetime),
+            pierce_left: stats.pierce,
+            hit: Vec::new(),
+        });
+        events.push(SimulationEvent::ProjectileFired { id });
+    }
+
+    /// Moves every projectile and checks the whole stretch it covered this
+    /// tick, so fast shots can't skip over a segment or a wall. Enemy shots
+    /// hit the snake and the snake's shots hit enemies.
+    pub(super) fn move_projectiles(&mut self, events: &mut Vec<SimulationEvent>) {
+        let delta = Self::timestep().as_secs_f32();
+        let head = *self.snake.head();
+        let enemies = self
+            .enemies
+            .iter()
+            .map(|enemy| (enemy.id, enemy.position))
+            .collect::<Vec<_>>();
+        let mut hits = Vec::new();
+        let mut enemy_hits: Vec<(EnemyId, usize)> = Vec::new();
+        let mut broken = Vec::new();
+
+        self.projectiles.retain_mut(|projectile| {
+            if projectile.lifetime.tick(Self::timestep()) {
+                return false;
+            }
+
+            let target = match projectile.owner {
+                Owner::Enemy => Some(head),
+                Owner::Snake => enemies
+                    .iter()
+                    .map(|(_, position)| *position)
+                    .min_by(|a, b| {
+                        let distance = |position: &Position| {
+                            projectile
+                                .position
+                                .distance_squared(Vec2::new(position.x as f32, position.y as f32))
+                        };
+
+                        distance(a)
+                            .partial_cmp(&distance(b))
+                            .unwrap_or(Ordering::Equal)
+                    }),
+            };
+
+            if let Some(target) = target {
+                projectile.home_in(target, delta);
+            }
+
+            let motion = projectile.direction * projectile.stats.speed * delta;
+            let radius = projectile.size / 2.;
+
+            for (_, tile, contact) in sweep(projectile.position, motion, radius) {
+                match contact {
+                    Contact::Body => {
+                        if projectile.hit.contains(&tile) {
+                            continue;
+                        }
+
+                        match projectile.owner {
+                            Owner::Enemy if self.snake.contains(&tile) => {
+                                hits.push((tile, projectile.damage));
+                            }
+                            Owner::Snake => {
+                                match enemies.iter().find(|(_, position)| *position == tile) {
+                                    Some((id, _)) => enemy_hits.push((*id, projectile.damage)),
+                                    None => continue,
+                                }
+                            }
+                            _ => continue,
+                        }
+
+                        projectile.hit.push(tile);
+
+                        if projectile.pierce_left == 0 {
+                            return false;
+                        }
+
+                        projectile.pierce_left -= 1;
+                    }
+                    Contact::Center => {
+                        if self.grid.tile(&tile) == Some(Tile::Crate) {
+                            broken.push(tile);
+                        }
+
+                        if self.grid.blocks_projectiles(&tile) {
+                            return false;
+                        }
+                    }
+                }
+            }
+
+            projectile.position += motion;
+            true
+        });
+
+        for position in broken {
+            // Two projectiles can reach the same crate on one tick
+            if self.grid.tile(&position) == Some(Tile::Crate) {
+                self.grid.set_tile(&position, Tile::Floor);
+                events.push(SimulationEvent::CrateBroken { position });
+            }
+        }
+
+        for (id, damage) in enemy_hits {
+            // The enemy may already have died to an earlier shot
+            if let Some(index) = self.enemies.iter().position(|enemy| enemy.id == id) {
+                let armor = self.config.enemies[self.enemies[index].kind.0].armor;
+
+                self.hurt_enemy(index, damage.saturating_sub(armor).max(1), events);
+            }
+        }
+
+        let deflect_chance = self.deflect_chance();
+
+        for (position, damage) in hits {
+            if self.game_over {
+                break;
+            }
+
+            if deflect_chance > 0 && self.rng.gen_range(0..100) < deflect_chance {
+                continue;
+            }
+
+            self.damage_snake(damage, position, events);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_finds_when_a_box_reaches_a_tile() {
+        let from = Vec2::ZERO;
+        let motion = Vec2::new(3., 0.);
+
+        assert_eq!(entry(from, motion, Position { x: 2, y: 0 }, 0.), Some(0.5));
+        assert_eq!(entry(from, motion, Position { x: 2, y: 1 }, 0.), None);
+        assert_eq!(entry(from, motion, Position { x: 4, y: 0 }, 0.), None);
+
+        // A wider shot grazes the row above, and touches tiles earlier
+        assert!(entry(from, motion, Position { x: 2, y: 1 }, 0.5).is_some());
+        assert_eq!(
+            entry(from, motion, Position { x: 2, y: 0 }, 0.5),
+            Some(1. / 3.)
+        );
+    }
+
+    #[test]
+    fn sweep_visits_every_tile_in_order() {
+        let centers = sweep(Vec2::ZERO, Vec2::new(5., 0.), 0.)
+            .into_iter()
+            .filter(|(_, _, contact)| *contact == Contact::Center)
+            .map(|(_, tile, _)| tile.x)
+            .collect::<Vec<_>>();
+
+        assert_eq!(centers, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn sweep_touches_neighbours_only_with_the_body() {
+        let contacts = sweep(Vec2::ZERO, Vec2::new(2., 0.), 0.25);
+        let above = Position { x: 1, y: 1 };
+
+        assert!(!contacts.iter().any(|(_, tile, _)| *tile == above));
+
+        let wide = sweep(Vec2::ZERO, Vec2::new(2., 0.), 0.5);
+
+        assert!(wide
+            .iter()
+            .any(|(_, tile, contact)| *tile == above && *contact == Contact::Body));
+        assert!(!wide
+            .iter()
+            .any(|(_, tile, contact)| *tile == above && *contact == Contact::Center));
+    }
+}
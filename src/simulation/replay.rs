@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    difficulty::{Difficulty, DifficultyCurve},
+    snake::Direction,
+    speed::SpeedConfig,
+    TickInput,
+};
+
+/// Bumped whenever a change to the format, to when inputs are recorded or to
+/// the simulation would make older replays play back differently.
+pub const REPLAY_VERSION: u32 = 3;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ReplayInput {
+    pub tick: u64,
+    pub direction: Direction,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ReplayPick {
+    pub tick: u64,
+    pub choice: usize,
+}
+
+/// Everything needed to play a run back: the seed, the difficulty the run was
+/// played on and every turn that was input along with the tick it was fed
+/// to. The speed and curve of the difficulty are kept as they were, so
+/// editing a preset later doesn't change how old runs play back. Turns are
+/// kept even when the snake ignored them, since enemies react to the turns
+/// the snake has queued up. Upgrade picks are kept the same way.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    /// Missing from replays saved before versions were kept, which reads as
+    /// 0.
+    #[serde(default)]
+    pub version: u32,
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub speed: SpeedConfig,
+    pub curve: DifficultyCurve,
+    pub inputs: Vec<ReplayInput>,
+    pub picks: Vec<ReplayPick>,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            seed,
+            difficulty: Difficulty::default(),
+            speed: SpeedConfig::default(),
+            curve: DifficultyCurve::default(),
+            inputs: Vec::new(),
+            picks: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, tick: u64, input: &TickInput) {
+        for direction in input.turns.iter() {
+            self.inputs.push(ReplayInput {
+                tick,
+                direction: *direction,
+            });
+        }
+
+        if let Some(choice) = input.pick {
+            self.picks.push(ReplayPick { tick, choice });
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, String> {
+        ron::to_string(self).map_err(|error| error.to_string())
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, String> {
+        let replay: Self = ron::from_str(text).map_err(|error| error.to_string())?;
+
+        if replay.version != REPLAY_VERSION {
+            return Err(format!(
+                "saved in replay format {}, this build plays format {}",
+                replay.version, REPLAY_VERSION
+            ));
+        }
+
+        Ok(replay)
+    }
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ReplayPlayer {
+    pub replay: Replay,
+    cursor: usize,
+    pick_cursor: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            cursor: 0,
+            pick_cursor: 0,
+        }
+    }
+
+    /// Starts feeding the inputs from the first tick again.
+    pub fn rewind(&mut self) {
+        self.cursor = 0;
+        self.pick_cursor = 0;
+    }
+
+    /// The input to feed the simulation for the given tick.
+    pub fn input(&mut self, tick: u64) -> TickInput {
+        let mut input = TickInput::default();
+
+        while let Some(recorded) = self.replay.inputs.get(self.cursor) {
+            if recorded.tick > tick {
+                break;
+            }
+
+            if recorded.tick == tick {
+                input.turns.push(recorded.direction);
+            }
+
+            self.cursor += 1;
+        }
+
+        while let Some(recorded) = self.replay.picks.get(self.pick_cursor) {
+            if recorded.tick > tick {
+                break;
+            }
+
+            if recorded.tick == tick {
+                input.pick = Some(recorded.choice);
+            }
+
+            self.pick_cursor += 1;
+        }
+
+        input
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{enemy::EnemyStats, Simulation, SimulationConfig, SimulationEvent};
+
+    const ENEMIES: &str = r#"#![enable(unwrap_variant_newtypes)]
+[
+    (
+        spawn_weight: 1,
+        decision_time: (0.4, 1.2),
+        behaviour: Ambusher(lead: 4),
+        attack: Melee,
+        wind_up: 1.0,
+        damage: 1,
+        move_step: 0.25,
+        health: 3,
+    ),
+    (
+        spawn_weight: 1,
+        decision_time: (0.5, 1.5),
+        behaviour: Skirmisher(min_range: 3, max_range: 9),
+        attack: Projectile(speed: 5.0),
+        wind_up: 0.5,
+        damage: 1,
+        move_step: 0.25,
+    ),
+]"#;
+
+    fn config() -> SimulationConfig {
+        SimulationConfig {
+            enemies: ron::from_str::<Vec<EnemyStats>>(ENEMIES).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    /// Plays a run with turns every so often, recording it as it goes.
+    fn play(seed: u64) -> (Replay, Vec<Vec<SimulationEvent>>) {
+        let mut simulation = Simulation::new(seed, config());
+        let mut replay = Replay::new(seed);
+        let mut events = Vec::new();
+        let turns = [
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+        ];
+
+        for tick in 1..=2000u64 {
+            let mut input = TickInput::default();
+
+            if tick % 23 == 0 {
+                input.turns.push(turns[(tick / 23) as usize % turns.len()]);
+            }
+
+            replay.record(simulation.ticks() + 1, &input);
+            events.push(simulation.tick(input));
+        }
+
+        (replay, events)
+    }
+
+    #[test]
+    fn replays_play_back_the_same_events() {
+        for seed in 0..5 {
+            let (replay, recorded) = play(seed);
+            let replay = Replay::from_ron(&replay.to_ron().unwrap()).unwrap();
+            let mut simulation = Simulation::new(replay.seed, config());
+            let mut player = ReplayPlayer::new(replay);
+
+            for events in recorded {
+                let input = player.input(simulation.ticks() + 1);
+
+                assert_eq!(simulation.tick(input), events);
+            }
+        }
+    }
+
+    #[test]
+    fn rewound_players_start_over() {
+        let (replay, recorded) = play(7);
+        let mut simulation = Simulation::new(replay.seed, config());
+        let mut player = ReplayPlayer::new(replay);
+
+        for _ in 0..500 {
+            player.input(simulation.ticks() + 1);
+            simulation.tick(TickInput::default());
+        }
+
+        player.rewind();
+        let mut simulation = Simulation::new(player.replay.seed, config());
+
+        for events in recorded {
+            let input = player.input(simulation.ticks() + 1);
+
+            assert_eq!(simulation.tick(input), events);
+        }
+    }
+
+    #[test]
+    fn replays_from_other_versions_are_refused() {
+        let mut replay = Replay::new(1);
+        replay.version = 0;
+
+        assert!(Replay::from_ron(&replay.to_ron().unwrap()).is_err());
+    }
+}
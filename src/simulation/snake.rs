@@ -0,0 +1,247 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use super::{weapon::Weapon, Position};
+
+/// A snake shorter than this is dead.
+const MIN_LENGTH: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+
+    pub fn step(&self, position: Position) -> Position {
+        match self {
+            Self::Up => Position {
+                x: position.x,
+                y: position.y + 1,
+            },
+            Self::Down => Position {
+                x: position.x,
+                y: position.y - 1,
+            },
+            Self::Left => Position {
+                x: position.x - 1,
+                y: position.y,
+            },
+            Self::Right => Position {
+                x: position.x + 1,
+                y: position.y,
+            },
+        }
+    }
+
+    /// The way that gets closest to `to` in one step, favouring the longer
+    /// axis.
+    pub fn towards(from: &Position, to: &Position) -> Self {
+        let (x, y) = (to.x - from.x, to.y - from.y);
+
+        match (x.abs() > y.abs(), x > 0, y > 0) {
+            (true, true, _) => Self::Right,
+            (true, false, _) => Self::Left,
+            (false, _, true) => Self::Up,
+            (false, _, false) => Self::Down,
+        }
+    }
+
+    fn between(from: &Position, to: &Position) -> Option<Self> {
+        match (to.x - from.x, to.y - from.y) {
+            (1, 0) => Some(Self::Right),
+            (-1, 0) => Some(Self::Left),
+            (0, 1) => Some(Self::Up),
+            (0, -1) => Some(Self::Down),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Direction {
+    fn default() -> Self {
+        Self::Right
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Snake {
+    pub segments: VecDeque<Position>,
+    /// What the snake attacks with on its own, in the order it got them.
+    pub weapons: Vec<Weapon>,
+    /// Whether the next hit is soaked up.
+    pub shielded: bool,
+    previous_tail: Option<Position>,
+    /// Segments still to come, added one per step as the tail moves on.
+    growth: usize,
+}
+
+impl Snake {
+    /// A three segment snake with its head on `head`, facing `direction`.
+    pub fn new(head: Position, direction: Direction) -> Self {
+        let behind = direction.opposite();
+        let neck = behind.step(head);
+
+        Self {
+            segments: VecDeque::from(vec![head, neck, behind.step(neck)]),
+            weapons: Vec::new(),
+            shielded: false,
+            previous_tail: None,
+            growth: 0,
+        }
+    }
+
+    pub fn head(&self) -> &Position {
+        self.segments.front().unwrap()
+    }
+
+    pub fn tail(&self) -> &Position {
+        self.segments.back().unwrap()
+    }
+
+    pub fn direction(&self) -> Option<Direction> {
+        let neck = self.segments.get(1)?;
+
+        Direction::between(neck, self.head())
+    }
+
+    pub fn contains(&self, position: &Position) -> bool {
+        self.segments.contains(position)
+    }
+
+    pub fn bit_itself(&self) -> bool {
+        let head = self.head();
+
+        self.segments.iter().skip(1).any(|segment| segment == head)
+    }
+
+    pub fn advance(&mut self, direction: Direction) {
+        let new_head = direction.step(*self.head());
+
+        self.segments.push_front(new_head);
+
+        if self.growth > 0 {
+            self.growth -= 1;
+            self.previous_tail = None;
+        } else {
+            self.previous_tail = self.segments.pop_back();
+        }
+    }
+
+    /// Grows the snake back onto the tile its tail just left. If that tile
+    /// is already taken, the segment is added on one of the next steps by
+    /// leaving the tail where it is.
+    pub fn grow(&mut self) {
+        match self.previous_tail.take() {
+            Some(tail) if !self.contains(&tail) => self.segments.push_back(tail),
+            _ => self.growth += 1,
+        }
+    }
+
+    /// Takes `amount` segments off the tail, unless a shield soaks up the
+    /// hit. Returns whether the hit got through. However hard the hit, the
+    /// snake keeps one segment less than it needs to live, so there is
+    /// always a head and a neck to draw.
+    pub fn damage(&mut self, amount: usize) -> bool {
+        if self.shielded {
+            self.shielded = false;
+            return false;
+        }
+
+        let length = self.segments.len().saturating_sub(amount);
+
+        self.segments.truncate(length.max(MIN_LENGTH - 1));
+
+        true
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.segments.len() < MIN_LENGTH
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snake(length: i32) -> Snake {
+        let mut snake = Snake::new(Position { x: 0, y: 0 }, Direction::Right);
+        snake.segments = (0..length).map(|x| Position { x: -x, y: 0 }).collect();
+        snake
+    }
+
+    #[test]
+    fn damage_takes_segments_off_the_tail() {
+        let mut snake = snake(6);
+
+        assert!(snake.damage(2));
+        assert_eq!(snake.segments.len(), 4);
+        assert_eq!(*snake.tail(), Position { x: -3, y: 0 });
+        assert!(!snake.is_dead());
+
+        assert!(snake.damage(1));
+        assert!(!snake.is_dead());
+    }
+
+    #[test]
+    fn heavy_damage_leaves_a_dead_head_and_neck() {
+        let mut snake = snake(4);
+
+        assert!(snake.damage(10));
+        assert_eq!(snake.segments.len(), 2);
+        assert_eq!(*snake.head(), Position { x: 0, y: 0 });
+        assert!(snake.is_dead());
+
+        snake.damage(1);
+        assert_eq!(snake.segments.len(), 2);
+    }
+
+    #[test]
+    fn shield_soaks_up_one_hit() {
+        let mut snake = snake(4);
+        snake.shielded = true;
+
+        assert!(!snake.damage(3));
+        assert_eq!(snake.segments.len(), 4);
+        assert!(!snake.shielded);
+        assert!(snake.damage(1));
+        assert_eq!(snake.segments.len(), 3);
+    }
+
+    #[test]
+    fn growth_waits_for_the_tail_to_move_on() {
+        let mut snake = snake(3);
+
+        snake.advance(Direction::Right);
+        snake.grow();
+        snake.grow();
+
+        assert_eq!(snake.segments.len(), 4);
+        assert_eq!(*snake.tail(), Position { x: -2, y: 0 });
+
+        snake.advance(Direction::Right);
+
+        assert_eq!(snake.segments.len(), 5);
+        assert_eq!(*snake.tail(), Position { x: -2, y: 0 });
+        assert!(!snake.bit_itself());
+    }
+}
@@ -0,0 +1,64 @@
+//! How fast the snake moves. The time between two steps is counted in ticks:
+//! it starts from the configured base scaled by the difficulty curve,
+//! shortens as the score climbs and is pushed either way by upgrades and
+//! boosts, within fixed bounds.
+
+use serde::{Deserialize, Serialize};
+
+use super::Simulation;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeedConfig {
+    /// Ticks between two snake steps at the start of a run.
+    pub step_ticks: u64,
+    /// The fewest ticks a step can be brought down to.
+    pub min_step_ticks: u64,
+    /// The most ticks a step can be slowed down to.
+    pub max_step_ticks: u64,
+    /// Every this many points take a tick off the step. 0 keeps the speed
+    /// the same however long the snake gets.
+    pub ramp_score: i32,
+}
+
+impl Default for SpeedConfig {
+    fn default() -> Self {
+        Self {
+            step_ticks: 5,
+            min_step_ticks: 3,
+            max_step_ticks: 10,
+            ramp_score: 20,
+        }
+    }
+}
+
+impl Simulation {
+    /// Ticks between two snake steps right now.
+    pub fn step_ticks(&self) -> u64 {
+        let speed = &self.config.speed;
+        let ramp = match speed.ramp_score {
+            0 => 0,
+            every => self.score() / every,
+        };
+        let base = (speed.step_ticks as f32 / self.difficulty().snake_speed.max(0.1)).round();
+        let faster = self.haste() as i64 + ramp as i64 + self.boost_haste();
+
+        (base as i64 - faster)
+            .min(speed.max_step_ticks as i64)
+            .max(speed.min_step_ticks as i64) as u64
+    }
+
+    /// Counts a tick towards the next snake step and returns whether it is
+    /// time to take it. Counting instead of checking the tick number keeps
+    /// the pace even while the step length changes.
+    pub(super) fn step_due(&mut self) -> bool {
+        self.step_counter += 1;
+
+        if self.step_counter < self.step_ticks() {
+            return false;
+        }
+
+        self.step_counter = 0;
+        true
+    }
+}
@@ -0,0 +1,280 @@
+//! Experience and upgrades. Every kill fills the experience bar; each level
+//! gained stops the run until one of three upgrades from the registry is
+//! picked. The snake and enemy code ask the simulation for the totals of the
+//! upgrades taken so far instead of keeping stats of their own.
+
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+use super::{weapon::WeaponStats, Simulation, SimulationEvent};
+
+/// How many upgrades a level-up offers to pick from.
+pub const OFFER_SIZE: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum Effect {
+    /// Takes this many ticks off the time between snake steps.
+    Haste(u64),
+    /// Extra segments grown for every kill.
+    Growth(usize),
+    /// The percent chance a projectile that hits the snake does no damage.
+    Deflect(usize),
+    /// Pickups this many tiles from the head are grabbed.
+    Magnet(usize),
+    /// Arms the snake with a weapon, or levels it up if it has it already.
+    Weapon(WeaponStats),
+}
+
+/// The gameplay half of an entry in the upgrade registry.
+#[derive(Clone, Debug, Deserialize)]
+pub struct UpgradeStats {
+    /// How many times the upgrade can be taken.
+    pub max_stacks: usize,
+    pub effect: Effect,
+}
+
+/// An index into the upgrades the simulation was created with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct UpgradeId(pub usize);
+
+/// The experience bar and what has been picked so far.
+#[derive(Clone, Debug)]
+pub struct Progression {
+    pub level: u32,
+    pub xp: u32,
+    stacks: Vec<usize>,
+    /// Level-ups that haven't been offered yet.
+    unspent: u32,
+    offer: Option<Vec<UpgradeId>>,
+}
+
+impl Progression {
+    pub fn new(upgrades: usize) -> Self {
+        Self {
+            level: 1,
+            xp: 0,
+            stacks: vec![0; upgrades],
+            unspent: 0,
+            offer: None,
+        }
+    }
+
+    /// Experience needed to get from the current level to the next.
+    pub fn xp_to_next(&self) -> u32 {
+        3 + 2 * self.level
+    }
+
+    pub fn stacks(&self, id: UpgradeId) -> usize {
+        self.stacks[id.0]
+    }
+
+    /// The upgrades to pick from while a level-up is waiting.
+    pub fn offer(&self) -> Option<&[UpgradeId]> {
+        self.offer.as_deref()
+    }
+}
+
+impl Simulation {
+    /// Adds up one kind of effect over every upgrade taken.
+    fn effect_total(&self, amount: impl Fn(&Effect) -> Option<usize>) -> usize {
+        self.config
+            .upgrades
+            .iter()
+            .zip(self.progression.stacks.iter())
+            .filter_map(|(upgrade, stacks)| amount(&upgrade.effect).map(|amount| amount * stacks))
+            .sum()
+    }
+
+    pub fn haste(&self) -> u64 {
+        self.effect_total(|effect| match effect {
+            Effect::Haste(ticks) => Some(*ticks as usize),
+            _ => None,
+        }) as u64
+    }
+
+    pub fn extra_growth(&self) -> usize {
+        self.effect_total(|effect| match effect {
+            Effect::Growth(segments) => Some(*segments),
+            _ => None,
+        })
+    }
+
+    pub fn magnet_bonus(&self) -> usize {
+        self.effect_total(|effect| match effect {
+            Effect::Magnet(range) => Some(*range),
+            _ => None,
+        })
+    }
+
+    pub fn deflect_chance(&self) -> usize {
+        self.effect_total(|effect| match effect {
+            Effect::Deflect(percent) => Some(*percent),
+            _ => None,
+        })
+    }
+
+    pub(super) fn gain_xp(&mut self, amount: u32, events: &mut Vec<SimulationEvent>) {
+        let progression = &mut self.progression;
+        progression.xp += amount;
+
+        while progression.xp >= progression.xp_to_next() {
+            progression.xp -= progression.xp_to_next();
+            progression.level += 1;
+            progression.unspent += 1;
+            events.push(SimulationEvent::LevelUp {
+                level: progression.level,
+            });
+        }
+
+        self.offer_upgrades(events);
+    }
+
+    /// Rolls the next offer if a level-up is waiting for one. Upgrades taken
+    /// as often as they can be are left out; with nothing left to offer the
+    /// level-up is simply spent.
+    fn offer_upgrades(&mut self, events: &mut Vec<SimulationEvent>) {
+        if self.progression.offer.is_some() || self.progression.unspent == 0 {
+            return;
+        }
+
+        self.progression.unspent -= 1;
+
+        let available = (0..self.config.upgrades.len())
+            .filter(|index| {
+                self.progression.stacks[*index] < self.config.upgrades[*index].max_stacks
+            })
+            .map(UpgradeId)
+            .collect::<Vec<_>>();
+        let offer = available
+            .choose_multiple(&mut self.rng, OFFER_SIZE)
+            .copied()
+            .collect::<Vec<_>>();
+
+        if offer.is_empty() {
+            return;
+        }
+
+        self.progression.offer = Some(offer);
+        events.push(SimulationEvent::UpgradeOffered);
+    }
+
+    pub fn is_choosing_upgrade(&self) -> bool {
+        self.progression.offer.is_some()
+    }
+
+    /// Takes one of the offered upgrades. Anything out of range is ignored
+    /// and the offer stays up.
+    pub(super) fn pick_upgrade(&mut self, choice: usize, events: &mut Vec<SimulationEvent>) {
+        let id = match self
+            .progression
+            .offer
+            .as_ref()
+            .and_then(|offer| offer.get(choice))
+        {
+            Some(id) => *id,
+            None => return,
+        };
+
+        self.progression.offer = None;
+        self.progression.stacks[id.0] += 1;
+
+        if let Effect::Weapon(stats) = self.config.upgrades[id.0].effect {
+            self.arm_weapon(id, &stats);
+        }
+
+        events.push(SimulationEvent::UpgradePicked { id });
+
+        self.offer_upgrades(events);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::SimulationConfig;
+
+    fn simulation(max_stacks: &[usize]) -> Simulation {
+        Simulation::new(
+            0,
+            SimulationConfig {
+                upgrades: max_stacks
+                    .iter()
+                    .map(|max_stacks| UpgradeStats {
+                        max_stacks: *max_stacks,
+                        effect: Effect::Growth(1),
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn level_up(simulation: &mut Simulation, levels: u32) -> Vec<SimulationEvent> {
+        let mut events = Vec::new();
+
+        for _ in 0..levels {
+            let xp = simulation.progression.xp_to_next() - simulation.progression.xp;
+            simulation.gain_xp(xp, &mut events);
+        }
+
+        events
+    }
+
+    #[test]
+    fn level_ups_offer_different_upgrades() {
+        let mut simulation = simulation(&[1, 1, 1, 1, 1]);
+        let events = level_up(&mut simulation, 1);
+
+        assert!(events.contains(&SimulationEvent::LevelUp { level: 2 }));
+        assert!(events.contains(&SimulationEvent::UpgradeOffered));
+
+        let mut offer = simulation.progression.offer().unwrap().to_vec();
+        offer.sort_by_key(|id| id.0);
+        offer.dedup();
+
+        assert_eq!(offer.len(), OFFER_SIZE);
+    }
+
+    #[test]
+    fn picks_out_of_range_keep_the_offer_up() {
+        let mut simulation = simulation(&[1, 1]);
+        let mut events = Vec::new();
+
+        level_up(&mut simulation, 1);
+        simulation.pick_upgrade(2, &mut events);
+
+        assert!(simulation.is_choosing_upgrade());
+        assert!(events.is_empty());
+
+        let id = simulation.progression.offer().unwrap()[1];
+        simulation.pick_upgrade(1, &mut events);
+
+        assert!(!simulation.is_choosing_upgrade());
+        assert_eq!(simulation.progression.stacks(id), 1);
+        assert_eq!(events, vec![SimulationEvent::UpgradePicked { id }]);
+    }
+
+    #[test]
+    fn maxed_upgrades_leave_the_draft() {
+        let mut simulation = simulation(&[1, 2]);
+
+        // Three level-ups at once are offered one after another
+        level_up(&mut simulation, 3);
+
+        for _ in 0..3 {
+            let offer = simulation.progression.offer().unwrap().to_vec();
+            let pick = offer.iter().position(|id| id.0 == 1).unwrap_or(0);
+
+            simulation.pick_upgrade(pick, &mut Vec::new());
+        }
+
+        assert_eq!(simulation.progression.stacks(UpgradeId(0)), 1);
+        assert_eq!(simulation.progression.stacks(UpgradeId(1)), 2);
+
+        // Nothing is left to offer, so the next level-up goes unspent
+        let events = level_up(&mut simulation, 1);
+
+        assert!(!events.contains(&SimulationEvent::UpgradeOffered));
+        assert!(!simulation.is_choosing_upgrade());
+    }
+}
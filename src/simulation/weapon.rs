@@ -0,0 +1,201 @@
+//! The snake's own attacks. Weapons come from upgrades: each one fires on
+//! its own cooldown whenever there is something to hit, and taking its
+//! upgrade again levels it up. Shots go through the same projectiles the
+//! enemies fire, only aimed the other way.
+
+use glam::Vec2;
+use serde::Deserialize;
+
+use super::{
+    pathfinding::distance,
+    projectile::{Owner, ProjectileStats},
+    upgrade::{Effect, UpgradeId},
+    Countdown, Position, Simulation, SimulationEvent,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum WeaponAttack {
+    /// Spits a projectile at the nearest enemy within `range`. Every level
+    /// adds a shot from further down the body.
+    Spit {
+        projectile: ProjectileStats,
+        damage: usize,
+        range: i32,
+    },
+    /// Hits every enemy within `reach` of the tail for `damage` per level.
+    Sweep { damage: usize, reach: i32 },
+    /// Lunges at an enemy straight ahead, up to `distance` tiles away plus
+    /// one per level. Only taken when the way there is clear.
+    Dash { distance: i32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct WeaponStats {
+    /// Seconds between two attacks.
+    pub cooldown: f32,
+    pub attack: WeaponAttack,
+}
+
+#[derive(Clone, Debug)]
+pub struct Weapon {
+    /// The upgrade the weapon came from, which also holds its stats.
+    pub upgrade: UpgradeId,
+    pub level: usize,
+    cooldown: Countdown,
+}
+
+impl Simulation {
+    /// Gives the snake the weapon of an upgrade, or levels it up if it
+    /// already has it.
+    pub(super) fn arm_weapon(&mut self, upgrade: UpgradeId, stats: &WeaponStats) {
+        match self
+            .snake
+            .weapons
+            .iter_mut()
+            .find(|weapon| weapon.upgrade == upgrade)
+        {
+            Some(weapon) => weapon.level += 1,
+            None => self.snake.weapons.push(Weapon {
+                upgrade,
+                level: 1,
+                cooldown: Countdown::once(stats.cooldown),
+            }),
+        }
+    }
+
+    pub(super) fn use_weapons(&mut self, events: &mut Vec<SimulationEvent>) {
+        for index in 0..self.snake.weapons.len() {
+            if self.game_over {
+                return;
+            }
+
+            let weapon = &mut self.snake.weapons[index];
+            weapon.cooldown.tick(Self::timestep());
+
+            if !weapon.cooldown.finished() {
+                continue;
+            }
+
+            let (upgrade, level) = (weapon.upgrade, weapon.level);
+            let stats = match self.config.upgrades[upgrade.0].effect {
+                Effect::Weapon(stats) => stats,
+                _ => continue,
+            };
+
+            // A weapon with nothing to hit stays ready
+            if self.attack_with(stats.attack, level, events) {
+                self.snake.weapons[index].cooldown = Countdown::once(stats.cooldown);
+            }
+        }
+    }
+
+    /// Returns whether the attack found something to hit.
+    fn attack_with(
+        &mut self,
+        attack: WeaponAttack,
+        level: usize,
+        events: &mut Vec<SimulationEvent>,
+    ) -> bool {
+        match attack {
+            WeaponAttack::Spit {
+                projectile,
+                damage,
+                range,
+            } => {
+                let length = self.snake.segments.len();
+                let mut fired = false;
+
+                for shot in 0..level {
+                    let from = self.snake.segments[shot * length / level];
+                    let target = self
+                        .enemies
+                        .iter()
+                        .map(|enemy| enemy.position)
+                        .filter(|position| distance(position, &from) <= range)
+                        .min_by_key(|position| distance(position, &from));
+
+                    if let Some(target) = target {
+                        let direction = (Vec2::new(target.x as f32, target.y as f32)
+                            - Vec2::new(from.x as f32, from.y as f32))
+                        .normalize_or_zero();
+
+                        self.fire_projectile(
+                            from,
+                            direction,
+                            projectile,
+                            damage,
+                            Owner::Snake,
+                            events,
+                        );
+                        fired = true;
+                    }
+                }
+
+                fired
+            }
+            WeaponAttack::Sweep { damage, reach } => {
+                let tail = *self.snake.tail();
+                let in_reach = |position: &Position| distance(position, &tail) <= reach;
+
+                if !self.enemies.iter().any(|enemy| in_reach(&enemy.position)) {
+                    return false;
+                }
+
+                events.push(SimulationEvent::TailWhipped { position: tail });
+
+                let mut index = 0;
+
+                while index < self.enemies.len() {
+                    if !in_reach(&self.enemies[index].position) {
+                        index += 1;
+                        continue;
+                    }
+
+                    let armor = self.config.enemies[self.enemies[index].kind.0].armor;
+                    let damage = (damage * level).saturating_sub(armor).max(1);
+
+                    if self.hurt_enemy(index, damage, events) {
+                        index += 1;
+                    }
+                }
+
+                true
+            }
+            WeaponAttack::Dash { distance } => {
+                let from = *self.snake.head();
+                let mut tile = from;
+                let mut steps = 0;
+
+                loop {
+                    if steps == distance + level as i32 - 1 {
+                        return false;
+                    }
+
+                    tile = self.direction.step(tile);
+                    steps += 1;
+
+                    if !self.grid.is_open(&tile) || self.snake.contains(&tile) {
+                        return false;
+                    }
+
+                    if self.enemies.iter().any(|enemy| enemy.position == tile) {
+                        break;
+                    }
+                }
+
+                events.push(SimulationEvent::Dashed { from, to: tile });
+
+                // The last step lands on the enemy and bites it
+                for _ in 0..steps {
+                    if self.game_over {
+                        break;
+                    }
+
+                    self.advance_snake(events);
+                }
+
+                true
+            }
+        }
+    }
+}
@@ -1,214 +1,96 @@
-use std::{collections::VecDeque, time::Duration};
+use std::collections::VecDeque;
 
-use bevy::{ecs::system::Command, prelude::*};
+use bevy::prelude::*;
 use bevy_kira_audio::prelude::*;
 use iyes_loopless::prelude::*;
 
 use crate::{
     despawn,
-    enemy::{Enemy, EnemyAttack},
-    music::Gameplay,
+    game::{Game, NextInput},
+    input::{ActionState, InputAction},
+    music::{Gameplay, Mixer, Sound},
+    pause::PauseState,
+    replay::Playback,
+    simulation::{
+        snake::{Direction, Snake},
+        DeathCause, SimulationEvent,
+    },
     AudioAssets, DestroyAfter, GameState, Position, TextureAssets,
 };
 
-const SNAKE_TIMESTEP: u64 = 125;
-
 pub struct SnakePlugin;
 
 impl Plugin for SnakePlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Snake>()
-            .init_resource::<Direction>()
-            .add_enter_system(GameState::Playing, reset_snake_system)
-            .add_fixed_timestep(Duration::from_millis(SNAKE_TIMESTEP), "snake")
-            .add_fixed_timestep_system(
-                "snake",
-                0,
-                draw_snake_system
-                    .run_in_state(GameState::Playing)
-                    .after("movement"),
-            )
-            .add_fixed_timestep_system(
-                "snake",
-                0,
-                move_snake_system
-                    .run_in_state(GameState::Playing)
-                    .label("movement"),
-            )
-            .add_system(input_system.run_in_state(GameState::Playing))
-            .add_system(growth_system.run_in_state(GameState::Playing))
-            .add_fixed_timestep_system(
-                "snake",
-                0,
-                collision_system
-                    .run_in_state(GameState::Playing)
-                    .after("movement"),
-            )
-            .add_fixed_timestep_system(
-                "snake",
-                0,
-                damage_system
-                    .run_in_state(GameState::Playing)
-                    .after("movement"),
-            )
-            .add_exit_system(GameState::Playing, despawn::<SnakeSegment>)
-            .add_system(
-                (|mut commands: Commands, keyboard_input: Res<Input<KeyCode>>| {
-                    if keyboard_input.just_pressed(KeyCode::Space) {
-                        commands.insert_resource(NextState(GameState::Playing));
-                    }
-                })
-                .run_in_state(GameState::GameOver),
-            );
-    }
-}
-
-#[derive(Resource)]
-pub struct Snake {
-    pub segments: VecDeque<Position>,
-}
-
-impl Default for Snake {
-    fn default() -> Self {
-        Self {
-            segments: VecDeque::from(vec![
-                Position { x: 0, y: 0 },
-                Position { x: -1, y: 0 },
-                Position { x: -2, y: 0 },
-            ]),
-        }
-    }
-}
-
-impl Snake {
-    pub fn head(&self) -> &Position {
-        self.segments.front().unwrap()
-    }
-
-    fn tail(&self) -> &Position {
-        self.segments.back().unwrap()
-    }
-
-    fn direction(&self) -> Result<Direction, ()> {
-        let head = self.head();
-        let one_before_head = if let Some(segment) = self.segments.get(1) {
-            segment
-        } else {
-            return Err(());
-        };
-
-        match (head.x - one_before_head.x, head.y - one_before_head.y) {
-            (1, 0) => Ok(Direction::Right),
-            (-1, 0) => Ok(Direction::Left),
-            (0, 1) => Ok(Direction::Up),
-            (0, -1) => Ok(Direction::Down),
-            _ => unreachable!(),
-        }
-    }
-
-    pub fn damage(&mut self, amount: usize) {
-        for _ in 0..amount {
-            self.segments.pop_back();
-        }
-    }
-
-    pub fn is_dead(&self) -> bool {
-        self.segments.len() < 3
-    }
-}
-
-struct AddSnakeSegment;
-
-impl Command for AddSnakeSegment {
-    fn write(self, world: &mut World) {
-        let snake = world.get_resource::<Snake>().unwrap().clone();
-        let tail = snake.segments.front().unwrap();
-        let one_before_tail = snake.segments.get(1).unwrap();
-
-        let new_tail = match (tail.x - one_before_tail.x, tail.y - one_before_tail.y) {
-            (1, 0) => Position {
-                x: tail.x - 1,
-                y: tail.y,
-            },
-            (-1, 0) => Position {
-                x: tail.x + 1,
-                y: tail.y,
-            },
-            (0, 1) => Position {
-                x: tail.x,
-                y: tail.y - 1,
-            },
-            (0, -1) => Position {
-                x: tail.x,
-                y: tail.y + 1,
-            },
-            _ => unreachable!(),
-        };
-
-        let mut snake = world.get_resource_mut::<Snake>().unwrap();
-        snake.segments.push_back(new_tail);
-    }
-}
-
-#[derive(Resource, Clone, PartialEq, Eq)]
-pub enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
-impl Direction {
-    fn opposite(&self) -> Self {
-        match self {
-            Self::Up => Self::Down,
-            Self::Down => Self::Up,
-            Self::Left => Self::Right,
-            Self::Right => Self::Left,
-        }
-    }
-}
-
-impl Default for Direction {
-    fn default() -> Self {
-        Self::Right
+        app.add_system(
+            input_system
+                .run_in_state(GameState::Playing)
+                .run_in_state(PauseState::Running)
+                .run_unless_resource_exists::<Playback>(),
+        )
+        .add_system_set(
+            ConditionSet::new()
+                .run_in_state(GameState::Playing)
+                .with_system(draw_snake_system)
+                .with_system(snake_event_system)
+                .into(),
+        )
+        .add_exit_system(GameState::Playing, despawn::<SnakeSegment>)
+        .add_system(
+            (|mut commands: Commands, action_state: Res<ActionState>| {
+                if action_state.just_pressed(InputAction::Confirm) {
+                    commands.insert_resource(NextState(GameState::Playing));
+                }
+            })
+            .run_in_state(GameState::GameOver),
+        );
     }
 }
 
 #[derive(Component)]
 struct SnakeSegment;
 
-fn reset_snake_system(mut snake: ResMut<Snake>, mut direction: ResMut<Direction>) {
-    *snake = Snake::default();
-    *direction = Direction::default();
-}
-
+/// Redraws the snake whenever its segments differ from the ones drawn last,
+/// whatever made them change.
 fn draw_snake_system(
-    snake: Res<Snake>,
+    game: Res<Game>,
+    mut drawn: Local<VecDeque<Position>>,
     mut commands: Commands,
     assets: Res<TextureAssets>,
     segment_entities: Query<Entity, With<SnakeSegment>>,
 ) {
+    if *drawn == game.snake.segments && !segment_entities.is_empty() {
+        return;
+    }
+
+    drawn.clone_from(&game.snake.segments);
+
     for entity in segment_entities.iter() {
         commands.entity(entity).despawn_recursive();
     }
 
-    snake.segments.iter().enumerate().for_each(|(i, x)| {
-        if x == snake.head() {
-            draw_snake_head(&mut commands, &assets, &snake);
-        } else if x == snake.tail() {
-            draw_snake_tail(&mut commands, &assets, &snake);
+    let snake = &game.snake;
+
+    let last = snake.segments.len().saturating_sub(1);
+
+    for i in 0..snake.segments.len() {
+        if i == 0 {
+            draw_snake_head(&mut commands, &assets, snake);
+        } else if i == last {
+            draw_snake_tail(&mut commands, &assets, snake);
         } else {
-            draw_snake_body(&mut commands, &assets, &snake, i);
+            draw_snake_body(&mut commands, &assets, snake, i);
         }
-    });
+    }
 }
 
 fn draw_snake_head(commands: &mut Commands, assets: &Res<TextureAssets>, snake: &Snake) {
     let head = snake.head();
     let mut transform = Transform::from_xyz(head.x as f32, head.y as f32, 2.);
 
-    let (x, y) = (head.x - snake.segments[1].x, head.y - snake.segments[1].y);
+    // A snake down to its head has nothing to face away from
+    let neck = snake.segments.get(1).unwrap_or(head);
+    let (x, y) = (head.x - neck.x, head.y - neck.y);
     let rotation = match (x, y) {
         (1, 0) => -std::f32::consts::FRAC_PI_2,
         (-1, 0) => std::f32::consts::FRAC_PI_2,
@@ -330,131 +212,75 @@ fn draw_snake_body(
     ));
 }
 
-fn move_snake_system(mut snake: ResMut<Snake>, direction: Res<Direction>) {
-    let mut new_head = snake.head().clone();
-
-    match *direction {
-        Direction::Up => new_head.y += 1,
-        Direction::Down => new_head.y -= 1,
-        Direction::Left => new_head.x -= 1,
-        Direction::Right => new_head.x += 1,
-    }
-
-    snake.segments.push_front(new_head);
-    snake.segments.pop_back();
-}
-
-fn input_system(
-    snake: Res<Snake>,
-    keyboard_input: Res<Input<KeyCode>>,
-    mut direction: ResMut<Direction>,
-) {
-    let mut new_direction = direction.clone();
-    let snake_direction = snake.direction();
-
-    if keyboard_input.pressed(KeyCode::W) | keyboard_input.pressed(KeyCode::Up) {
-        new_direction = Direction::Up;
-    }
-    if keyboard_input.pressed(KeyCode::S) | keyboard_input.pressed(KeyCode::Down) {
-        new_direction = Direction::Down;
-    }
-    if keyboard_input.pressed(KeyCode::A) | keyboard_input.pressed(KeyCode::Left) {
-        new_direction = Direction::Left;
-    }
-    if keyboard_input.pressed(KeyCode::D) | keyboard_input.pressed(KeyCode::Right) {
-        new_direction = Direction::Right;
-    }
-
-    if let Ok(snake_direction) = snake_direction {
-        if new_direction != snake_direction.opposite() {
-            *direction = new_direction;
-        }
-    }
-}
-
-fn growth_system(
-    mut commands: Commands,
-    snake: Res<Snake>,
-    enemies: Query<(Entity, &Position), With<Enemy>>,
-    audio_assets: Res<AudioAssets>,
-    gameplay_channel: Res<AudioChannel<Gameplay>>,
-) {
-    let head = snake.head();
-
-    for (entity, enemy) in enemies.iter() {
-        if head == enemy {
-            commands.entity(entity).despawn();
-            commands.add(AddSnakeSegment);
-
-            gameplay_channel
-                .play(audio_assets.eat.clone())
-                .with_volume(0.5);
+fn input_system(action_state: Res<ActionState>, mut input: ResMut<NextInput>) {
+    let turns = [
+        (InputAction::Up, Direction::Up),
+        (InputAction::Down, Direction::Down),
+        (InputAction::Left, Direction::Left),
+        (InputAction::Right, Direction::Right),
+    ];
+
+    for (action, direction) in turns {
+        if action_state.just_pressed(action) {
+            input.turns.push(direction);
         }
     }
 }
 
-fn collision_system(
+fn snake_event_system(
     mut commands: Commands,
-    snake: Res<Snake>,
+    mut events: EventReader<SimulationEvent>,
+    texture_assets: Res<TextureAssets>,
     audio_assets: Res<AudioAssets>,
+    mixer: Res<Mixer>,
     gameplay_channel: Res<AudioChannel<Gameplay>>,
 ) {
-    let head = snake.head();
-
-    let mut kill_snake = || {
-        gameplay_channel.play(audio_assets.death_by_bumping.clone());
-        commands.insert_resource(NextState(GameState::GameOver));
+    let play = |sound: Sound| {
+        gameplay_channel
+            .play(sound.handle(&audio_assets))
+            .with_volume(mixer.gain(sound));
     };
 
-    if !head.in_world() {
-        kill_snake();
-    }
-
-    for segment in snake.segments.iter().skip(1) {
-        if head == segment {
-            kill_snake();
+    for event in events.iter() {
+        match event {
+            SimulationEvent::SnakeAte { .. } => {
+                play(Sound::Eat);
+            }
+            SimulationEvent::EnemyHit { position, .. }
+            | SimulationEvent::EnemyKilled { position, .. } => {
+                play(Sound::Eat);
+                spawn_hit_effect(&mut commands, &texture_assets, position);
+            }
+            SimulationEvent::TailWhipped { position } => {
+                spawn_hit_effect(&mut commands, &texture_assets, position);
+            }
+            SimulationEvent::SnakeHit { position }
+            | SimulationEvent::ShieldBroken { position }
+            | SimulationEvent::BombExploded { position, .. } => {
+                play(Sound::Hit);
+                spawn_hit_effect(&mut commands, &texture_assets, position);
+            }
+            SimulationEvent::SnakeDied {
+                cause: DeathCause::Bumped,
+            } => {
+                play(Sound::DeathByBumping);
+            }
+            _ => {}
         }
     }
 }
 
-fn damage_system(
-    mut commands: Commands,
-    mut snake: ResMut<Snake>,
-    enemy_attacks: Query<(Entity, &Transform), With<EnemyAttack>>,
-    texture_assets: Res<TextureAssets>,
-    audio_assets: Res<AudioAssets>,
-    gameplay_channel: Res<AudioChannel<Gameplay>>,
-) {
-    for (entity, transform) in enemy_attacks.iter() {
-        let transform_to_check =
-            transform.translation + (Vec3::new(0.5, 0.5, 0.) * transform.rotation.to_scaled_axis());
-        let enemy_attack_position = Position::from(transform_to_check.truncate());
-
-        if snake.segments.contains(&enemy_attack_position) {
-            snake.damage(1);
-            commands.entity(entity).despawn();
-            gameplay_channel.play(audio_assets.hit.clone());
-
-            commands.spawn((
-                SpriteBundle {
-                    texture: texture_assets.effect.clone(),
-                    transform: Transform::from_xyz(
-                        enemy_attack_position.x as f32,
-                        enemy_attack_position.y as f32,
-                        3.,
-                    ),
-                    sprite: Sprite {
-                        custom_size: Some(Vec2::new(2., 2.)),
-                        ..default()
-                    },
-                    ..default()
-                },
-                DestroyAfter(Timer::from_seconds(0.25, TimerMode::Once)),
-            ));
-
-            if snake.is_dead() {
-                commands.insert_resource(NextState(GameState::GameOver));
-            }
-        }
-    }
+fn spawn_hit_effect(commands: &mut Commands, assets: &TextureAssets, position: &Position) {
+    commands.spawn((
+        SpriteBundle {
+            texture: assets.effect.clone(),
+            transform: Transform::from_xyz(position.x as f32, position.y as f32, 3.),
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(2., 2.)),
+                ..default()
+            },
+            ..default()
+        },
+        DestroyAfter(Timer::from_seconds(0.25, TimerMode::Once)),
+    ));
 }
@@ -0,0 +1,54 @@
+//! Saves small text files: into the platform data directory on native and
+//! into `localStorage` on the web, keyed by the same relative path.
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(key: &str) -> Result<std::path::PathBuf, String> {
+    directories::ProjectDirs::from("", "", "snake_survivors")
+        .map(|dirs| dirs.data_dir().join(key))
+        .ok_or_else(|| "Could not find a data directory".to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load(key: &str) -> Result<Option<String>, String> {
+    let path = path(key)?;
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    std::fs::read_to_string(path)
+        .map(Some)
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(key: &str, contents: &str) -> Result<(), String> {
+    let path = path(key)?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+    }
+
+    std::fs::write(path, contents).map_err(|error| error.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Result<web_sys::Storage, String> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| "localStorage is not available".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load(key: &str) -> Result<Option<String>, String> {
+    local_storage()?
+        .get_item(key)
+        .map_err(|_| format!("Could not read {}", key))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save(key: &str, contents: &str) -> Result<(), String> {
+    local_storage()?
+        .set_item(key, contents)
+        .map_err(|_| format!("Could not write {}", key))
+}
@@ -0,0 +1,307 @@
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use iyes_loopless::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    despawn,
+    game::{Game, NextInput},
+    input::{ActionState, InputAction},
+    pause::PauseState,
+    replay::Playback,
+    simulation::upgrade::{UpgradeStats, OFFER_SIZE},
+    DataAssets, GameState, UiAssets,
+};
+
+const XP_BAR_WIDTH: f32 = 320.;
+const PICK_ACTIONS: [InputAction; OFFER_SIZE] =
+    [InputAction::Pick1, InputAction::Pick2, InputAction::Pick3];
+
+#[derive(Deserialize)]
+pub struct UpgradeDefinition {
+    pub name: String,
+    pub description: String,
+    pub stats: UpgradeStats,
+}
+
+/// Every upgrade a level-up can offer, read from an `.upgrades.ron` file.
+/// The simulation gets the stats in the same order, so an `UpgradeId`
+/// indexes straight into `upgrades`.
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "3c1f7d52-9a4b-4e86-b0d3-6e2a5f8c1b47"]
+pub struct UpgradeRegistry {
+    pub upgrades: Vec<UpgradeDefinition>,
+}
+
+impl UpgradeRegistry {
+    pub fn stats(&self) -> Vec<UpgradeStats> {
+        self.upgrades
+            .iter()
+            .map(|upgrade| upgrade.stats.clone())
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct UpgradeRegistryLoader;
+
+impl AssetLoader for UpgradeRegistryLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let registry = ron::de::from_bytes::<UpgradeRegistry>(bytes)?;
+
+            load_context.set_default_asset(LoadedAsset::new(registry));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["upgrades.ron"]
+    }
+}
+
+pub struct UpgradePlugin;
+
+impl Plugin for UpgradePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<UpgradeRegistry>()
+            .init_asset_loader::<UpgradeRegistryLoader>()
+            .add_enter_system(GameState::Playing, spawn_xp_bar)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Playing)
+                    .with_system(xp_bar_system)
+                    .with_system(upgrade_menu_system)
+                    .into(),
+            )
+            .add_system(
+                pick_upgrade_system
+                    .run_in_state(GameState::Playing)
+                    .run_in_state(PauseState::Running)
+                    .run_unless_resource_exists::<Playback>(),
+            )
+            .add_exit_system(GameState::Playing, despawn::<XpBar>)
+            .add_exit_system(GameState::Playing, despawn::<UpgradeMenu>);
+    }
+}
+
+#[derive(Component)]
+struct XpBar;
+
+#[derive(Component)]
+struct XpBarFill;
+
+#[derive(Component)]
+struct LevelText;
+
+#[derive(Component)]
+struct UpgradeMenu;
+
+/// The position of the upgrade in the current offer.
+#[derive(Component)]
+struct UpgradeButton(usize);
+
+fn spawn_xp_bar(mut commands: Commands, ui_assets: Res<UiAssets>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    justify_content: JustifyContent::FlexStart,
+                    align_items: AlignItems::FlexEnd,
+                    flex_direction: FlexDirection::Column,
+                    position_type: PositionType::Absolute,
+                    padding: UiRect::all(Val::Px(16.)),
+                    ..default()
+                },
+                ..default()
+            },
+            XpBar,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "LV 1",
+                    TextStyle {
+                        font: ui_assets.font.clone(),
+                        font_size: 28.,
+                        color: Color::WHITE,
+                    },
+                ),
+                LevelText,
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(XP_BAR_WIDTH), Val::Px(10.)),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(0.), Val::Percent(100.)),
+                                ..default()
+                            },
+                            background_color: Color::rgb(0.3, 0.8, 0.3).into(),
+                            ..default()
+                        },
+                        XpBarFill,
+                    ));
+                });
+        });
+}
+
+fn xp_bar_system(
+    game: Res<Game>,
+    mut fill_query: Query<&mut Style, With<XpBarFill>>,
+    mut text_query: Query<&mut Text, With<LevelText>>,
+) {
+    let progression = game.progression();
+    let fill = progression.xp as f32 / progression.xp_to_next() as f32;
+
+    for mut style in fill_query.iter_mut() {
+        style.size.width = Val::Px(XP_BAR_WIDTH * fill);
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("LV {}", progression.level);
+    }
+}
+
+/// Puts the offer up while the simulation waits for a pick and takes it
+/// down again once one is made.
+fn upgrade_menu_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    data_assets: Res<DataAssets>,
+    registries: Res<Assets<UpgradeRegistry>>,
+    ui_assets: Res<UiAssets>,
+    menu_query: Query<Entity, With<UpgradeMenu>>,
+) {
+    let offer = match game.progression().offer() {
+        Some(offer) => offer,
+        None => {
+            for entity in menu_query.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+
+            return;
+        }
+    };
+
+    if !menu_query.is_empty() {
+        return;
+    }
+
+    let registry = match registries.get(&data_assets.upgrades) {
+        Some(registry) => registry,
+        None => return,
+    };
+
+    let text_style = |font_size: f32| TextStyle {
+        font: ui_assets.font.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.6).into(),
+                ..default()
+            },
+            UpgradeMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section("LEVEL UP", text_style(64.)));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect {
+                            top: Val::Px(20.),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (index, id) in offer.iter().enumerate() {
+                        let upgrade = &registry.upgrades[id.0];
+
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        flex_direction: FlexDirection::Column,
+                                        size: Size::new(Val::Px(240.), Val::Px(140.)),
+                                        margin: UiRect::all(Val::Px(10.)),
+                                        padding: UiRect::all(Val::Px(10.)),
+                                        ..default()
+                                    },
+                                    background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                                    ..default()
+                                },
+                                UpgradeButton(index),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    format!("{}. {}", index + 1, upgrade.name.to_uppercase()),
+                                    text_style(28.),
+                                ));
+                                parent.spawn(TextBundle::from_section(
+                                    &upgrade.description,
+                                    text_style(18.),
+                                ));
+                            });
+                    }
+                });
+        });
+}
+
+fn pick_upgrade_system(
+    game: Res<Game>,
+    action_state: Res<ActionState>,
+    button_query: Query<(&Interaction, &UpgradeButton), Changed<Interaction>>,
+    mut input: ResMut<NextInput>,
+) {
+    if !game.is_choosing_upgrade() {
+        return;
+    }
+
+    let clicked = button_query
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Clicked)
+        .map(|(_, button)| button.0);
+    let pressed = PICK_ACTIONS
+        .iter()
+        .position(|action| action_state.just_pressed(*action));
+
+    if let Some(choice) = clicked.or(pressed) {
+        input.pick = Some(choice);
+    }
+}
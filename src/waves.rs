@@ -0,0 +1,224 @@
+use std::collections::HashSet;
+
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use iyes_loopless::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    despawn,
+    enemy::EnemyRoster,
+    game::Game,
+    simulation::{
+        director::{DirectorConfig, Wave},
+        enemy::{EnemyId, EnemyKind},
+        SimulationEvent,
+    },
+    DataAssets, DestroyAfter, GameState, UiAssets,
+};
+
+#[derive(Deserialize)]
+struct WaveDefinition {
+    breather: f32,
+    interval: f32,
+    clear_time: f32,
+    /// Archetype names from the enemy roster with how many of each to send.
+    enemies: Vec<(String, usize)>,
+}
+
+/// The waves of a `.waves.ron` file. Enemies are named rather than indexed,
+/// so they are only matched up with the roster when a run starts.
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "b0a8e0c4-5f62-4d0e-9a55-2f3c6a8d71e9"]
+pub struct WavesAsset {
+    telegraph: f32,
+    min_distance: i32,
+    max_alive: usize,
+    waves: Vec<WaveDefinition>,
+}
+
+impl WavesAsset {
+    pub fn director_config(&self, roster: &EnemyRoster) -> DirectorConfig {
+        let kind = |name: &str| {
+            let index = roster
+                .archetypes
+                .iter()
+                .position(|archetype| archetype.name == name);
+
+            if index.is_none() {
+                error!("Wave enemy {} is not in the enemy roster", name);
+            }
+
+            index.map(EnemyKind)
+        };
+
+        DirectorConfig {
+            telegraph: self.telegraph,
+            min_distance: self.min_distance,
+            max_alive: self.max_alive,
+            waves: self
+                .waves
+                .iter()
+                .map(|wave| Wave {
+                    breather: wave.breather,
+                    interval: wave.interval,
+                    clear_time: wave.clear_time,
+                    enemies: wave
+                        .enemies
+                        .iter()
+                        .filter_map(|(name, count)| kind(name).map(|kind| vec![kind; *count]))
+                        .flatten()
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct WavesLoader;
+
+impl AssetLoader for WavesLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let waves = ron::de::from_bytes::<WavesAsset>(bytes)?;
+
+            load_context.set_default_asset(LoadedAsset::new(waves));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["waves.ron"]
+    }
+}
+
+pub struct WavesPlugin;
+
+impl Plugin for WavesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<WavesAsset>()
+            .init_asset_loader::<WavesLoader>()
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Playing)
+                    .with_system(draw_spawn_markers_system)
+                    .with_system(wave_banner_system)
+                    .into(),
+            )
+            .add_exit_system(GameState::Playing, despawn::<SpawnMarker>)
+            .add_exit_system(GameState::Playing, despawn::<WaveBanner>);
+    }
+}
+
+/// Shows where an enemy is about to appear.
+#[derive(Component)]
+struct SpawnMarker(EnemyId);
+
+#[derive(Component)]
+struct WaveBanner;
+
+fn marker_color(progress: f32) -> Color {
+    Color::rgba(1., 1., 1., 0.15 + 0.5 * progress)
+}
+
+fn draw_spawn_markers_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    data_assets: Res<DataAssets>,
+    rosters: Res<Assets<EnemyRoster>>,
+    mut marker_query: Query<(Entity, &SpawnMarker, &mut TextureAtlasSprite)>,
+) {
+    let mut drawn = HashSet::new();
+
+    for (entity, marker, mut sprite) in marker_query.iter_mut() {
+        match game
+            .pending_spawns
+            .iter()
+            .find(|spawn| spawn.id == marker.0)
+        {
+            Some(spawn) => sprite.color = marker_color(spawn.progress()),
+            None => {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        }
+
+        drawn.insert(marker.0);
+    }
+
+    let roster = match rosters.get(&data_assets.enemies) {
+        Some(roster) => roster,
+        None => return,
+    };
+
+    for spawn in game.pending_spawns.iter() {
+        if drawn.contains(&spawn.id) {
+            continue;
+        }
+
+        commands.spawn((
+            SpawnMarker(spawn.id),
+            SpriteSheetBundle {
+                texture_atlas: roster.archetypes[spawn.kind.0].sprite_sheet.clone(),
+                transform: Transform::from_xyz(
+                    spawn.position.x as f32,
+                    spawn.position.y as f32,
+                    1.4,
+                ),
+                sprite: TextureAtlasSprite {
+                    color: marker_color(spawn.progress()),
+                    custom_size: Some(Vec2::new(1., 1.)),
+                    ..default()
+                },
+                ..default()
+            },
+        ));
+    }
+}
+
+fn wave_banner_system(
+    mut commands: Commands,
+    mut events: EventReader<SimulationEvent>,
+    ui_assets: Res<UiAssets>,
+) {
+    for event in events.iter() {
+        let wave = match event {
+            SimulationEvent::WaveStarted { wave } => wave,
+            _ => continue,
+        };
+
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                },
+                WaveBanner,
+                DestroyAfter(Timer::from_seconds(1.5, TimerMode::Once)),
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    format!("WAVE {}", wave),
+                    TextStyle {
+                        font: ui_assets.font.clone(),
+                        font_size: 64.,
+                        color: Color::WHITE,
+                    },
+                ));
+            });
+    }
+}